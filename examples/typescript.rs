@@ -0,0 +1,33 @@
+//! Example that writes a TypeScript module with url functions for the named routes in a route tree.
+//! Pass the file to write to as an argument, the module is written to stdout otherwise.
+
+use std::{env, fs};
+use tide::{Request, Result};
+use tide_fluent_routes::prelude::*;
+use tide_fluent_routes::typescript::typescript_module;
+
+fn main() {
+    let routes = root::<()>()
+        .name("index")
+        .get(endpoint)
+        .at("articles", |route| {
+            route
+                .name("articles")
+                .get(endpoint)
+                .post(endpoint)
+                .at(":id", |route| route.name("article").get(endpoint))
+        })
+        .expect("Error setting up routes");
+
+    let module = typescript_module(&routes.reverse_router().expect("Invalid route names"))
+        .expect("Route names that become the same function name");
+
+    match env::args().nth(1) {
+        Some(file) => fs::write(file, module).expect("Error writing module"),
+        None => print!("{}", module),
+    }
+}
+
+async fn endpoint(_: Request<()>) -> Result {
+    todo!()
+}
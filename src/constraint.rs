@@ -0,0 +1,294 @@
+//! Constraints on the values of path parameters. Constraints can be added inline in paths as a
+//! regular expression between parentheses like `:id(\d+)` or with `RouteBuilder::at_matching`.
+//! When the value of a parameter does not match its constraint routing falls through to sibling
+//! routes for the same path, the reverse router refuses to resolve urls with values that do not
+//! match;
+//! ```rust
+//! # use tide::{Request, Result};
+//! # async fn endpoint(_: Request<()>) -> Result {
+//! #     todo!()
+//! # }
+//! use tide_fluent_routes::prelude::*;
+//!
+//! let mut server = tide::Server::new();
+//!
+//! server.register(
+//!     root()
+//!         .at("articles", |route| route
+//!             .at_matching(":id", Constraint::Numeric, |route| route.get(endpoint))
+//!             .at(":slug([a-z-]+)", |route| route.get(endpoint))
+//!         )
+//! ).expect("Error setting up routes");
+//! ```
+//! Routes with constrained parameters and the routes that overlap them are dispatched by the route
+//! tree instead of by tide, these routes get a clone of the server state taken on the first request they handle.
+
+use crate::error::RouteError;
+use crate::path::Path;
+use crate::router::Router;
+use crate::util::{ArcMiddleware, BoxedEndpoint};
+use crate::Result;
+use regex::Regex;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use tide::http::Method;
+use tide::{utils::async_trait, Endpoint, Request, Response, StatusCode};
+
+/// A constraint on the value of a path parameter
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    /// Only ascii digits
+    Numeric,
+
+    /// Only ascii letters and digits
+    Alphanumeric,
+
+    /// One of a fixed set of values
+    OneOf(Vec<String>),
+
+    /// Matches a regular expression, the whole value has to match
+    Pattern(Regex),
+}
+
+impl Constraint {
+    /// Construct a constraint from a regular expression, the expression has to match the whole
+    /// value of the parameter
+    pub fn pattern(pattern: &str) -> Result<Self> {
+        match Regex::new(&format!("^(?:{})$", pattern)) {
+            Ok(regex) => Ok(Constraint::Pattern(regex)),
+            Err(error) => Err(RouteError::InvalidConstraint {
+                path: pattern.to_string(),
+                message: error.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Check if a value matches the constraint
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Constraint::Numeric => !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()),
+            Constraint::Alphanumeric => {
+                !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric())
+            }
+            Constraint::OneOf(values) => values.iter().any(|allowed| allowed == value),
+            Constraint::Pattern(regex) => regex.is_match(value),
+        }
+    }
+
+    /// The constraint as a regular expression like it is written inline in a path
+    pub(crate) fn inline(&self) -> String {
+        match self {
+            Constraint::Numeric => "[0-9]+".to_string(),
+            Constraint::Alphanumeric => "[a-zA-Z0-9]+".to_string(),
+            Constraint::OneOf(values) => values
+                .iter()
+                .map(|value| regex::escape(value))
+                .collect::<Vec<_>>()
+                .join("|"),
+            Constraint::Pattern(regex) => {
+                let pattern = regex.as_str();
+                pattern
+                    .strip_prefix("^(?:")
+                    .and_then(|pattern| pattern.strip_suffix(")$"))
+                    .unwrap_or(pattern)
+                    .to_string()
+            }
+        }
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constraint::Numeric, Constraint::Numeric) => true,
+            (Constraint::Alphanumeric, Constraint::Alphanumeric) => true,
+            (Constraint::OneOf(values), Constraint::OneOf(others)) => values == others,
+            (Constraint::Pattern(regex), Constraint::Pattern(other)) => {
+                regex.as_str() == other.as_str()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Constraint::Numeric => write!(formatter, "numeric"),
+            Constraint::Alphanumeric => write!(formatter, "alphanumeric"),
+            Constraint::OneOf(values) => write!(formatter, "one of {}", values.join(", ")),
+            Constraint::Pattern(regex) => write!(formatter, "matching {}", regex.as_str()),
+        }
+    }
+}
+
+/// An endpoint for a path with constrained parameters, the path with its middleware and endpoint.
+/// A fallback is a catch-all that only answers methods the path has no endpoint for.
+pub(crate) struct ConstrainedRoute<State> {
+    pub(crate) path: Path,
+    pub(crate) method: Option<Method>,
+    pub(crate) middleware: Vec<ArcMiddleware<State>>,
+    pub(crate) endpoint: BoxedEndpoint<State>,
+    pub(crate) fallback: bool,
+}
+
+/// Dispatches requests for routes that tide sees as the same route because they only differ in
+/// parameter names and constraints, together with the routes that overlap them. Every path gets its own tide server so each route keeps its
+/// own middleware, params and method handling, tide doesn't allow running a middleware chain or
+/// setting params outside a server. Tide endpoints have no access to the state before the first
+/// request, so the servers are built when the first request comes in.
+pub(crate) struct ConstraintDispatch<State> {
+    servers: Mutex<Servers<State>>,
+}
+
+enum Servers<State> {
+    Pending(Vec<ConstrainedRoute<State>>),
+    Ready(Arc<Vec<PathServer<State>>>),
+}
+
+/// A tide server with all endpoints for a single path
+struct PathServer<State> {
+    path: Path,
+    methods: Vec<Option<Method>>,
+    fallback: bool,
+    server: tide::Server<State>,
+}
+
+impl<State> PathServer<State> {
+    /// Check if the server has an endpoint for the method, tide falls back to GET for HEAD. A
+    /// fallback only answers the methods the server has no endpoint for, so it doesn't count.
+    fn handles(&self, method: Method) -> bool {
+        self.methods.iter().any(|handled| match handled {
+            None => !self.fallback,
+            Some(handled) => {
+                *handled == method || (method == Method::Head && *handled == Method::Get)
+            }
+        })
+    }
+}
+
+impl<State: Clone + Send + Sync + 'static> ConstraintDispatch<State> {
+    /// Dispatch requests to the routes, more specific paths are tried first
+    pub(crate) fn new(mut routes: Vec<ConstrainedRoute<State>>) -> Self {
+        routes.sort_by(|route, other| other.path.cmp_specificity(&route.path));
+
+        Self {
+            servers: Mutex::new(Servers::Pending(routes)),
+        }
+    }
+
+    fn servers(&self, state: &State) -> Arc<Vec<PathServer<State>>> {
+        let mut servers = self
+            .servers
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        let routes = match &mut *servers {
+            Servers::Ready(servers) => return servers.clone(),
+            Servers::Pending(routes) => mem::take(routes),
+        };
+
+        let mut path_servers: Vec<PathServer<State>> = Vec::new();
+        for route in routes {
+            let index = match path_servers
+                .iter()
+                .position(|server| server.path == route.path)
+            {
+                Some(index) => index,
+                None => {
+                    path_servers.push(PathServer {
+                        path: route.path.clone(),
+                        methods: Vec::new(),
+                        fallback: false,
+                        server: tide::Server::with_state(state.clone()),
+                    });
+                    path_servers.len() - 1
+                }
+            };
+
+            let path_server = &mut path_servers[index];
+            path_server.methods.push(route.method);
+            path_server.fallback |= route.fallback;
+            path_server.server.register_endpoint(
+                &route.path.to_string(),
+                route.method,
+                &route.middleware,
+                route.endpoint,
+            );
+        }
+
+        let path_servers = Arc::new(path_servers);
+        *servers = Servers::Ready(path_servers.clone());
+        path_servers
+    }
+}
+
+impl<State> Debug for ConstraintDispatch<State> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        formatter.debug_struct("ConstraintDispatch").finish()
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for ConstraintDispatch<State> {
+    async fn call(&self, request: Request<State>) -> tide::Result {
+        let servers = self.servers(request.state());
+        let path = request.url().path().to_string();
+
+        let matching: Vec<_> = servers
+            .iter()
+            .filter(|server| server.path.matches(&path))
+            .collect();
+
+        // Use the first path with an endpoint for the method, otherwise let the first matching path
+        // respond with a method not allowed
+        let server = matching
+            .iter()
+            .find(|server| server.handles(request.method()))
+            .or_else(|| matching.first());
+
+        // Calling the server keeps errors in the response for middleware outside the dispatch,
+        // the params of the route this dispatch was registered on have generated names
+        match server {
+            Some(server) => server.server.call(request).await,
+            None => Ok(Response::new(StatusCode::NotFound)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_match_numeric() {
+        assert!(Constraint::Numeric.matches("123"));
+        assert!(!Constraint::Numeric.matches("12a"));
+        assert!(!Constraint::Numeric.matches(""));
+    }
+
+    #[test]
+    fn should_match_one_of() {
+        let constraint = Constraint::OneOf(vec!["new".to_string(), "edit".to_string()]);
+
+        assert!(constraint.matches("edit"));
+        assert!(!constraint.matches("editor"));
+    }
+
+    #[test]
+    fn should_match_whole_value_against_pattern() {
+        let constraint = Constraint::pattern(r"\d+|new").unwrap();
+
+        assert!(constraint.matches("42"));
+        assert!(constraint.matches("new"));
+        assert!(!constraint.matches("42a"));
+        assert!(!constraint.matches("renew"));
+    }
+
+    #[test]
+    fn should_fail_on_invalid_pattern() {
+        assert!(Constraint::pattern(r"\d+(").is_err());
+    }
+}
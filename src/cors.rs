@@ -0,0 +1,399 @@
+//! Cross-origin resource sharing for parts of a route tree. `cors` adds CORS headers to the
+//! responses of all routes below it and registers an OPTIONS endpoint answering preflight requests
+//! for every path that doesn't define OPTIONS itself. Preflight responses list the methods that are
+//! defined for the path. A nested policy replaces the policy of its parent, also when both policies
+//! are the same. When the trailing slash policy redirects, preflight requests are answered for both
+//! forms of a path because browsers don't follow redirects for preflight requests;
+//! ```rust
+//! # use tide::{Request, Result};
+//! # async fn endpoint(_: Request<()>) -> Result {
+//! #     todo!()
+//! # }
+//! use tide_fluent_routes::prelude::*;
+//!
+//! let mut server = tide::Server::new();
+//!
+//! server.register(
+//!     root().at("api", |route| route
+//!         .cors(CorsPolicy::new(), |route| route
+//!             .at("public", |route| route.get(endpoint))
+//!         )
+//!         .cors(
+//!             CorsPolicy::new()
+//!                 .allow_origin("https://admin.example.com")
+//!                 .allow_header("content-type")
+//!                 .allow_credentials(),
+//!             |route| route.at("internal", |route| route.get(endpoint).post(endpoint)),
+//!         )
+//!     )
+//! ).expect("Error setting up routes");
+//! ```
+
+use crate::constraint::ConstrainedRoute;
+use crate::metadata::RouteMetadataExt;
+use crate::path::Path;
+use crate::util::BoxedEndpoint;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tide::http::headers::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE,
+    ACCESS_CONTROL_REQUEST_METHOD, ORIGIN, VARY,
+};
+use tide::http::Method;
+use tide::{utils::async_trait, Endpoint, Middleware, Next, Request, Response, StatusCode};
+
+/// The origins, headers and credentials that cross-origin requests are allowed to use
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorsPolicy {
+    origins: Vec<String>,
+    headers: Vec<String>,
+    expose_headers: Vec<String>,
+    credentials: bool,
+    max_age: Option<Duration>,
+    id: usize,
+}
+
+/// The id of the next `cors` segment
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+impl CorsPolicy {
+    /// Construct a policy that allows requests from any origin
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allow requests from the listed origins, call once for every origin
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        self.origins.push(origin.to_string());
+        self
+    }
+
+    /// Allow requests to send a header
+    pub fn allow_header(mut self, header: &str) -> Self {
+        self.headers.push(header.to_string());
+        self
+    }
+
+    /// Allow scripts to read a response header
+    pub fn expose_header(mut self, header: &str) -> Self {
+        self.expose_headers.push(header.to_string());
+        self
+    }
+
+    /// Allow requests with cookies and authorization headers
+    pub fn allow_credentials(mut self) -> Self {
+        self.credentials = true;
+        self
+    }
+
+    /// Let browsers cache preflight responses
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// The same policy with the id of a new `cors` segment, the middleware of a segment finds its
+    /// own policy in the route metadata by this id
+    pub(crate) fn with_unique_id(mut self) -> Self {
+        self.id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        self
+    }
+
+    /// The value of the allow origin header for an origin, `None` when the origin is not allowed.
+    /// Credentials can not be used with a wildcard so the origin is sent back in that case.
+    fn allowed_origin(&self, origin: &str) -> Option<String> {
+        if self.origins.is_empty() {
+            if self.credentials {
+                Some(origin.to_string())
+            } else {
+                Some("*".to_string())
+            }
+        } else if self.origins.iter().any(|allowed| allowed == origin) {
+            Some(origin.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Add the headers for an allowed origin to a response, returns if the origin was allowed
+    fn apply(&self, origin: Option<&str>, response: &mut Response) -> bool {
+        if !self.origins.is_empty() || self.credentials {
+            response.append_header(VARY, "Origin");
+        }
+
+        let allowed = match origin.and_then(|origin| self.allowed_origin(origin)) {
+            Some(allowed) => allowed,
+            None => return false,
+        };
+
+        response.insert_header(ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+        if self.credentials {
+            response.insert_header(ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+        }
+        true
+    }
+}
+
+/// Adds CORS headers to responses of the routes below a `cors` segment. Only the middleware of
+/// the innermost policy for a route adds headers, it is found through the id of the policy in the
+/// route metadata.
+#[derive(Debug)]
+pub(crate) struct CorsMiddleware(CorsPolicy);
+
+impl CorsMiddleware {
+    pub(crate) fn new(policy: CorsPolicy) -> Self {
+        Self(policy)
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for CorsMiddleware {
+    async fn handle(&self, request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let applies = request
+            .route_metadata::<CorsPolicy>()
+            .map_or(false, |policy| policy.id == self.0.id);
+        let origin = request
+            .header(ORIGIN)
+            .map(|origin| origin.as_str().to_string());
+
+        let mut response = next.run(request).await;
+
+        if applies
+            && self.0.apply(origin.as_deref(), &mut response)
+            && !self.0.expose_headers.is_empty()
+        {
+            response.insert_header(
+                ACCESS_CONTROL_EXPOSE_HEADERS,
+                self.0.expose_headers.join(", "),
+            );
+        }
+        Ok(response)
+    }
+}
+
+/// Add an OPTIONS endpoint answering preflight requests to every path with a policy that has no
+/// OPTIONS endpoint yet, the first policy for a path is used
+pub(crate) fn preflight<State: Clone + Send + Sync + 'static>(
+    routes: &mut Vec<ConstrainedRoute<State>>,
+    policies: Vec<(Path, CorsPolicy)>,
+) {
+    let mut done: Vec<Path> = Vec::new();
+
+    for (path, policy) in policies {
+        if done.contains(&path) {
+            continue;
+        }
+
+        let methods: Vec<Option<Method>> = routes
+            .iter()
+            .filter(|route| route.path == path)
+            .map(|route| route.method)
+            .collect();
+
+        if !methods.contains(&Some(Method::Options)) {
+            // Catch-all endpoints accept any method, so the requested method is allowed
+            let methods = if methods.contains(&None) {
+                None
+            } else {
+                let mut methods: Vec<String> =
+                    methods.iter().flatten().map(Method::to_string).collect();
+                methods.sort();
+                Some(methods.join(", "))
+            };
+
+            routes.push(ConstrainedRoute {
+                path: path.clone(),
+                method: Some(Method::Options),
+                middleware: Vec::new(),
+                endpoint: BoxedEndpoint::new(PreflightEndpoint { policy, methods }),
+                fallback: false,
+            });
+        }
+        done.push(path);
+    }
+}
+
+/// Responds to preflight requests for a path with the methods of the path
+#[derive(Debug)]
+struct PreflightEndpoint {
+    policy: CorsPolicy,
+    methods: Option<String>,
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for PreflightEndpoint {
+    async fn call(&self, request: Request<State>) -> tide::Result {
+        let mut response = Response::new(StatusCode::NoContent);
+        let origin = request.header(ORIGIN).map(|origin| origin.as_str());
+
+        if self.policy.apply(origin, &mut response) {
+            let requested = request
+                .header(ACCESS_CONTROL_REQUEST_METHOD)
+                .map(|method| method.as_str());
+
+            if let Some(methods) = self.methods.as_deref().or(requested) {
+                response.insert_header(ACCESS_CONTROL_ALLOW_METHODS, methods);
+            }
+            if !self.policy.headers.is_empty() {
+                response
+                    .insert_header(ACCESS_CONTROL_ALLOW_HEADERS, self.policy.headers.join(", "));
+            }
+            if let Some(max_age) = self.policy.max_age {
+                response.insert_header(ACCESS_CONTROL_MAX_AGE, max_age.as_secs().to_string());
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::test_util::{endpoint, request};
+    use tide::http::headers::HeaderName;
+    use tide::http::{self, Url};
+
+    fn server() -> tide::Server<()> {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("api", |r| {
+                r.cors(CorsPolicy::new(), |r| {
+                    r.at("public", |r| r.get(endpoint)).cors(
+                        CorsPolicy::new()
+                            .allow_origin("https://admin.example.com")
+                            .allow_header("content-type")
+                            .expose_header("x-total")
+                            .allow_credentials(),
+                        |r| r.at("internal", |r| r.get(endpoint).delete(endpoint)),
+                    )
+                })
+                .at("private", |r| r.get(endpoint))
+            }))
+            .unwrap();
+        server
+    }
+
+    async fn call(server: &tide::Server<()>, method: Method, path: &str) -> http::Response {
+        let mut request = request(method, path);
+        request.insert_header(ORIGIN, "https://admin.example.com");
+        request.insert_header(ACCESS_CONTROL_REQUEST_METHOD, "DELETE");
+
+        server.respond(request).await.unwrap()
+    }
+
+    fn header(response: &http::Response, name: HeaderName) -> Option<&str> {
+        response.header(name).map(|value| value.as_str())
+    }
+
+    #[async_std::test]
+    async fn should_answer_preflight_with_methods_of_path() {
+        let server = server();
+
+        let response = call(&server, Method::Options, "/api/public").await;
+        assert_eq!(response.status(), StatusCode::NoContent);
+        assert_eq!(header(&response, ACCESS_CONTROL_ALLOW_ORIGIN), Some("*"));
+        assert_eq!(header(&response, ACCESS_CONTROL_ALLOW_METHODS), Some("GET"));
+
+        let response = call(&server, Method::Options, "/api/internal").await;
+        assert_eq!(
+            header(&response, ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some("https://admin.example.com")
+        );
+        assert_eq!(
+            header(&response, ACCESS_CONTROL_ALLOW_METHODS),
+            Some("DELETE, GET")
+        );
+        assert_eq!(
+            header(&response, ACCESS_CONTROL_ALLOW_HEADERS),
+            Some("content-type")
+        );
+        assert_eq!(
+            header(&response, ACCESS_CONTROL_ALLOW_CREDENTIALS),
+            Some("true")
+        );
+    }
+
+    #[async_std::test]
+    async fn should_add_headers_of_innermost_policy() {
+        let server = server();
+
+        let response = call(&server, Method::Get, "/api/public").await;
+        assert_eq!(header(&response, ACCESS_CONTROL_ALLOW_ORIGIN), Some("*"));
+        assert_eq!(header(&response, ACCESS_CONTROL_EXPOSE_HEADERS), None);
+
+        let response = call(&server, Method::Get, "/api/internal").await;
+        assert_eq!(
+            header(&response, ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some("https://admin.example.com")
+        );
+        assert_eq!(
+            header(&response, ACCESS_CONTROL_EXPOSE_HEADERS),
+            Some("x-total")
+        );
+    }
+
+    #[async_std::test]
+    async fn should_not_allow_other_origins() {
+        let server = server();
+        let url = Url::parse("http://example.com/api/internal").unwrap();
+        let mut request = http::Request::new(Method::Options, url);
+        request.insert_header(ORIGIN, "https://evil.example.com");
+
+        let response: http::Response = server.respond(request).await.unwrap();
+        assert_eq!(header(&response, ACCESS_CONTROL_ALLOW_ORIGIN), None);
+        assert_eq!(header(&response, ACCESS_CONTROL_ALLOW_METHODS), None);
+    }
+
+    #[async_std::test]
+    async fn should_only_apply_innermost_of_equal_policies() {
+        let policy = CorsPolicy::new().allow_origin("https://admin.example.com");
+        let mut server = tide::Server::new();
+        server
+            .register(root().cors(policy.clone(), |r| {
+                r.cors(policy, |r| r.at("articles", |r| r.get(endpoint)))
+            }))
+            .unwrap();
+
+        let response = call(&server, Method::Get, "/articles").await;
+        assert_eq!(response[VARY].iter().count(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_answer_preflight_for_redirected_form() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().cors(CorsPolicy::new(), |r| {
+                    r.at("articles", |r| r.delete(endpoint))
+                }),
+                RegisterOptions::new().trailing_slash(TrailingSlash::PermanentRedirect),
+            )
+            .unwrap();
+
+        let response = call(&server, Method::Options, "/articles/").await;
+        assert_eq!(response.status(), StatusCode::NoContent);
+        assert_eq!(
+            header(&response, ACCESS_CONTROL_ALLOW_METHODS),
+            Some("DELETE")
+        );
+
+        let response = call(&server, Method::Delete, "/articles/").await;
+        assert_eq!(response.status(), StatusCode::PermanentRedirect);
+    }
+
+    #[async_std::test]
+    async fn should_leave_routes_outside_policy_alone() {
+        let server = server();
+
+        let response = call(&server, Method::Get, "/api/private").await;
+        assert_eq!(header(&response, ACCESS_CONTROL_ALLOW_ORIGIN), None);
+        assert_eq!(
+            call(&server, Method::Options, "/api/private")
+                .await
+                .status(),
+            StatusCode::MethodNotAllowed
+        );
+    }
+}
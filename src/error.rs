@@ -0,0 +1,336 @@
+//! Errors that can occur while building and registering a route tree and resolving named routes
+
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::panic::Location;
+use tide::http::Method;
+
+/// Errors in the definition of a route tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteError {
+    /// More than one endpoint was added for the same path and method
+    Conflicts(Vec<Conflict>),
+
+    /// A name was given to a route segment that already has a name
+    AlreadyNamed {
+        /// The name the segment already has
+        name: String,
+
+        /// The name that was rejected
+        rejected: String,
+    },
+
+    /// A namespace was given to a route segment that already has a namespace
+    AlreadyNamespaced {
+        /// The namespace the segment already has
+        namespace: String,
+
+        /// The namespace that was rejected
+        rejected: String,
+    },
+
+    /// The same name was used for more than one route
+    DuplicateNames(Vec<DuplicateName>),
+
+    /// A constraint on a path parameter is invalid
+    InvalidConstraint {
+        /// The path or pattern the constraint was added to
+        path: String,
+
+        /// Describes what is wrong with the constraint
+        message: String,
+    },
+
+    /// A path fragment passed to `at` is not a valid path pattern
+    InvalidPath {
+        /// The path fragment that was rejected
+        fragment: String,
+
+        /// Describes what is wrong with the path
+        message: String,
+    },
+
+    /// A segment was named after a typed route with parameters that differ from the path
+    TypedRouteParams {
+        /// The name of the typed route
+        name: String,
+
+        /// The path of the route segment
+        path: String,
+
+        /// The parameters of the typed route
+        params: Vec<String>,
+    },
+
+    /// Route names that become the same function name in a generated TypeScript module
+    DuplicateFunctionNames {
+        /// The function name
+        function: String,
+
+        /// The route names that become the function name
+        names: Vec<String>,
+    },
+
+    /// Documentation was added to a route segment without endpoints
+    NothingToDocument {
+        /// The path of the route segment
+        path: String,
+    },
+
+    /// Routes overlap or can not be reached, only reported when registering in strict mode
+    Lints(Vec<Lint>),
+}
+
+impl Display for RouteError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            RouteError::Conflicts(conflicts) => {
+                write!(formatter, "conflicting endpoints in route tree:")?;
+                for conflict in conflicts {
+                    write!(formatter, "\n  {}", conflict)?;
+                }
+                Ok(())
+            }
+            RouteError::AlreadyNamed { name, rejected } => write!(
+                formatter,
+                "can not name route {}, it is already named {}",
+                rejected, name
+            ),
+            RouteError::AlreadyNamespaced {
+                namespace,
+                rejected,
+            } => write!(
+                formatter,
+                "can not add namespace {} to route, it already has namespace {}",
+                rejected, namespace
+            ),
+            RouteError::DuplicateNames(duplicates) => {
+                write!(formatter, "duplicate route names in route tree:")?;
+                for duplicate in duplicates {
+                    write!(formatter, "\n  {}", duplicate)?;
+                }
+                Ok(())
+            }
+            RouteError::InvalidConstraint { path, message } => {
+                write!(formatter, "invalid constraint in {}: {}", path, message)
+            }
+            RouteError::InvalidPath { fragment, message } => {
+                write!(formatter, "invalid path {}: {}", fragment, message)
+            }
+            RouteError::TypedRouteParams { name, path, params } => write!(
+                formatter,
+                "typed route {} with parameters {} does not match path {}",
+                name,
+                params.join(", "),
+                path
+            ),
+            RouteError::DuplicateFunctionNames { function, names } => write!(
+                formatter,
+                "routes {} all become function {}",
+                names.join(", "),
+                function
+            ),
+            RouteError::NothingToDocument { path } => write!(
+                formatter,
+                "can not add documentation to {}, no endpoint was added yet",
+                path
+            ),
+            RouteError::Lints(lints) => {
+                write!(formatter, "ambiguous routes in route tree:")?;
+                for lint in lints {
+                    write!(formatter, "\n  {}", lint)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for RouteError {}
+
+/// Endpoints that are registered for the same path and method
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// The path the endpoints are registered on
+    pub path: String,
+
+    /// The method the endpoints are registered for, `None` for catch-all endpoints
+    pub method: Option<Method>,
+
+    /// The places in the source where each of the endpoints were added to the route tree
+    pub locations: Vec<&'static Location<'static>>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        match &self.method {
+            Some(method) => write!(formatter, "{} {} declared at ", method, self.path)?,
+            None => write!(formatter, "catch-all {} declared at ", self.path)?,
+        }
+
+        for (index, location) in self.locations.iter().enumerate() {
+            if index > 0 {
+                write!(formatter, ", ")?;
+            }
+            write!(formatter, "{}", location)?;
+        }
+        Ok(())
+    }
+}
+
+/// A warning about routes that overlap or can not be reached
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    /// Some requests for a path also match a more specific path that takes precedence
+    Overlap {
+        /// The less specific path
+        path: String,
+
+        /// The more specific path that takes precedence
+        overlapping: String,
+    },
+
+    /// An endpoint can never be reached because an endpoint for the same method on a path that
+    /// only differs in parameter names matches every request first
+    Unreachable {
+        /// The path of the unreachable endpoint
+        path: String,
+
+        /// The method of the unreachable endpoint, `None` for catch-all endpoints
+        method: Option<Method>,
+
+        /// The path of the endpoint that is matched instead
+        shadowed_by: String,
+    },
+}
+
+impl Display for Lint {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Lint::Overlap { path, overlapping } => write!(
+                formatter,
+                "{} overlaps {}, {} takes precedence",
+                path, overlapping, overlapping
+            ),
+            Lint::Unreachable {
+                path,
+                method,
+                shadowed_by,
+            } => {
+                match method {
+                    Some(method) => write!(formatter, "{} {}", method, path)?,
+                    None => write!(formatter, "catch-all {}", path)?,
+                }
+                write!(
+                    formatter,
+                    " is unreachable, it is shadowed by {}",
+                    shadowed_by
+                )
+            }
+        }
+    }
+}
+
+/// A route name that is used for more than one route
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateName {
+    /// The duplicate name
+    pub name: String,
+
+    /// The paths of all routes with this name
+    pub paths: Vec<String>,
+}
+
+impl Display for DuplicateName {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            formatter,
+            "{} is used for {}",
+            self.name,
+            self.paths.join(", ")
+        )
+    }
+}
+
+/// Errors resolving a named route
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    /// There is no route with this name
+    UnknownRoute(String),
+
+    /// A parameter in the path of the route has no value
+    MissingParam {
+        /// The name of the route
+        route: String,
+
+        /// The name of the parameter
+        param: String,
+    },
+
+    /// A parameter in the path of the route has an empty value
+    EmptyParam {
+        /// The name of the route
+        route: String,
+
+        /// The name of the parameter
+        param: String,
+    },
+
+    /// The value of a parameter does not satisfy the constraint on that parameter
+    InvalidParam {
+        /// The name of the route
+        route: String,
+
+        /// The name of the parameter
+        param: String,
+    },
+
+    /// Parameters were passed that do not appear in the path of the route
+    UnusedParams {
+        /// The name of the route
+        route: String,
+
+        /// The names of the unused parameters
+        params: Vec<String>,
+    },
+
+    /// No reverse router is available in the request, routes need to be registered with
+    /// `RegisterOptions::with_reverse_router` to use the reverse router from requests
+    NoReverseRouter,
+
+    /// No base url is configured for resolving absolute urls
+    NoBaseUrl,
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ResolveError::UnknownRoute(route) => write!(formatter, "unknown route {}", route),
+            ResolveError::MissingParam { route, param } => {
+                write!(formatter, "missing parameter {} for route {}", param, route)
+            }
+            ResolveError::EmptyParam { route, param } => {
+                write!(formatter, "empty parameter {} for route {}", param, route)
+            }
+            ResolveError::InvalidParam { route, param } => {
+                write!(
+                    formatter,
+                    "invalid value for parameter {} for route {}",
+                    param, route
+                )
+            }
+            ResolveError::UnusedParams { route, params } => write!(
+                formatter,
+                "unused parameters {} for route {}",
+                params.join(", "),
+                route
+            ),
+            ResolveError::NoReverseRouter => {
+                write!(formatter, "no reverse router available in request")
+            }
+            ResolveError::NoBaseUrl => write!(formatter, "no base url configured"),
+        }
+    }
+}
+
+impl Error for ResolveError {}
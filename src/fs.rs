@@ -5,6 +5,7 @@ use async_std::path::PathBuf as AsyncPathBuf;
 use log;
 use std::ffi::OsStr;
 use std::io;
+use std::panic::Location;
 use std::path::Path;
 use tide::Body;
 use tide::Response;
@@ -14,12 +15,15 @@ use tide::{Request, Result, StatusCode};
 /// Extension methods for the routebuilder to serving files and directories
 pub trait ServeFs<State: Clone + Send + Sync + 'static>: RouteBuilder<State> {
     /// Serve a directory at a location
+    #[track_caller]
     fn serve_dir(self, dir_path: impl AsRef<Path>) -> io::Result<Self> {
+        let location = Location::caller();
         let endpoint = ServeDir::serve(dir_path, "*path")?;
-        Ok(self.at("*path", |route| route.get(endpoint)))
+        Ok(self.at("*path", |route| route.get(endpoint).located_at(location)))
     }
 
     /// Same as serve_dir, but for a single file
+    #[track_caller]
     fn serve_file(self, file_path: impl AsRef<Path>) -> io::Result<Self> {
         Ok(self.get(ServeFile::serve(file_path)?))
     }
@@ -57,7 +61,7 @@ impl<State: Clone + Send + Sync + 'static> Endpoint<State> for ServeDir {
             } else if path_segment == OsStr::new("..") {
                 file_path.pop();
             } else {
-                file_path.push(&path_segment);
+                file_path.push(path_segment);
             }
         }
 
@@ -120,11 +120,49 @@
 //!         )
 //!         .at("api/v2", |route| route
 //!             .get(endpoint)
-//!             .get(endpoint)
+//!             .post(endpoint)
 //!         ),
 //! );
 //! ```
 //!
+//! Registering more than one endpoint for the same path and method is an error, `register` returns
+//! an error listing every conflicting path and method and where each endpoint was declared. Tests
+//! that need to replace endpoints can opt out of this using `register_with` and
+//! `RegisterOptions::allow_overrides`.
+//!
+//! Routes can be named, a reverse router built from the route tree resolves names to urls.
+//! Registering with `RegisterOptions::with_reverse_router` makes the reverse router available to
+//! endpoints through the `UrlFor` extension trait on requests;
+//! ```rust
+//! # use tide::{Request, Result};
+//! use tide_fluent_routes::{params, prelude::*};
+//!
+//! async fn article(request: Request<()>) -> Result {
+//!     let comments = request.url_for("comments", params! {"id" => request.param("id")?})?;
+//!     Ok(comments.into())
+//! }
+//! # async fn endpoint(_: Request<()>) -> Result {
+//! #     todo!()
+//! # }
+//!
+//! let mut server = tide::Server::new();
+//!
+//! server.register_with(
+//!     root()
+//!         .at("articles/:id", |route| route
+//!             .name("article")
+//!             .get(article)
+//!             .at("comments", |route| route
+//!                 .name("comments")
+//!                 .get(endpoint)
+//!             )
+//!         ),
+//!     RegisterOptions::new().with_reverse_router(),
+//! ).expect("Error setting up routes");
+//! ```
+//! The named routes can also be served as json using `serve_route_table`, this allows frontends to
+//! build urls from the same route names.
+//!
 //! Serving directories is possible using `serve_dir`, this works the same as with normal Tide routes,
 //! fluent routes adds the `serve_file` convenience method for serving single files.
 //! ```rust,no_run
@@ -154,12 +192,24 @@
     unused_qualifications
 )]
 
+pub mod constraint;
+pub mod cors;
+pub mod error;
 pub mod fs;
-mod path;
+pub mod listing;
+pub mod metadata;
+mod methods;
+pub mod openapi;
+pub mod param;
+pub mod path;
 pub mod reverse_router;
 pub mod routebuilder;
 pub mod router;
 mod routesegment;
+#[cfg(test)]
+mod test_util;
+pub mod trailing_slash;
+pub mod typescript;
 mod util;
 
 use std::collections::HashMap;
@@ -170,15 +220,21 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 /// Import types to use tide_fluent_routes
 pub mod prelude {
-    pub use super::reverse_router::ReverseRouter;
-    pub use super::routebuilder::{RouteBuilder, RouteBuilderExt};
-    pub use super::router::Router;
-    pub use super::routesegment::{root, RouteSegment, SubRoute};
+    pub use super::constraint::Constraint;
+    pub use super::cors::CorsPolicy;
+    pub use super::metadata::RouteMetadataExt;
+    pub use super::param::TypedParamExt;
+    pub use super::reverse_router::{Params, ReverseRouter, TypedRoute, UrlFor};
+    pub use super::routebuilder::{RouteBuilder, RouteBuilderExt, RouteDocsExt};
+    pub use super::router::{RegisterOptions, Router};
+    pub use super::routesegment::{root, RouteInfo, RouteSegment, SubRoute};
+    pub use super::trailing_slash::TrailingSlash;
     pub use tide::http::Method;
 }
 
 #[cfg(test)]
 mod test {
+    use crate::error::{DuplicateName, RouteError};
     use crate::prelude::*;
     use crate::util::ArcMiddleware;
     use std::future::Future;
@@ -226,7 +282,7 @@ mod test {
         // TODO: Fix this, possibly with a named endpoint
         // assert_eq!(routes.get(0).unwrap().route, Some(Method::Get));
         assert_eq!(
-            routes.get(0).unwrap().path.to_string(),
+            routes.first().unwrap().path.to_string(),
             "/path/subpath".to_string()
         );
     }
@@ -234,7 +290,7 @@ mod test {
     #[test]
     fn should_start_path_with_slash() {
         let routes: Vec<_> = root::<()>().get(|_| async { Ok("") }).unwrap().build();
-        assert_eq!(routes.get(0).unwrap().path.to_string(), "/".to_string());
+        assert_eq!(routes.first().unwrap().path.to_string(), "/".to_string());
     }
 
     fn middleware<'a>(
@@ -261,7 +317,257 @@ mod test {
             .unwrap()
             .build();
 
-        assert_eq!(routes.get(0).unwrap().middleware.len(), 1);
+        assert_eq!(routes.first().unwrap().middleware.len(), 1);
         assert_eq!(routes.get(1).unwrap().middleware.len(), 2);
     }
+
+    fn duplicate_names(error: crate::Error) -> Vec<DuplicateName> {
+        match error.downcast::<RouteError>() {
+            Ok(RouteError::DuplicateNames(duplicates)) => duplicates,
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_fail_naming_a_named_segment() {
+        let error = root::<()>().name("first").name("second").unwrap_err();
+
+        assert_eq!(
+            error.downcast::<RouteError>().unwrap(),
+            RouteError::AlreadyNamed {
+                name: "first".to_string(),
+                rejected: "second".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn should_pass_naming_error_up_the_tree() {
+        let result = root::<()>().at("path", |r| {
+            r.at("subpath", |r| r.name("first").name("second"))
+                .get(|_| async { Ok("") })
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_report_duplicate_names() {
+        let error = root::<()>()
+            .name("root")
+            .at("first", |r| r.name("duplicate"))
+            .at("second", |r| r.at("third", |r| r.name("duplicate")))
+            .unwrap()
+            .reverse_router()
+            .unwrap_err();
+
+        assert_eq!(
+            duplicate_names(error),
+            vec![DuplicateName {
+                name: "duplicate".to_string(),
+                paths: vec!["/first".to_string(), "/second/third".to_string()]
+            }]
+        );
+    }
+
+    #[test]
+    fn should_fail_registering_duplicate_names() {
+        let mut server = tide::Server::new();
+        let result = server.register(
+            root()
+                .at("first", |r| r.name("duplicate").get(|_| async { Ok("") }))
+                .at("second", |r| r.name("duplicate").get(|_| async { Ok("") })),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_resolve_named_route_from_tree() {
+        let router = root::<()>()
+            .at("articles", |r| {
+                r.name("articles")
+                    .at(":id", |r| r.name("article").get(|_| async { Ok("") }))
+            })
+            .unwrap()
+            .reverse_router()
+            .unwrap();
+
+        let mut params = Params::new();
+        params.insert("id", 12);
+
+        assert_eq!(
+            router.resolve("article", params),
+            Ok("/articles/12".to_string())
+        );
+    }
+
+    fn article_routes(routes: SubRoute<()>) -> SubRoute<()> {
+        routes
+            .name("index")
+            .at(":id", |r| r.name("show").get(|_| async { Ok("") }))
+    }
+
+    #[test]
+    fn should_prefix_names_with_namespaces() {
+        let router = root::<()>()
+            .name("root")
+            .at("api/v1", |r| {
+                r.namespace("api.v1")
+                    .at("articles", |r| article_routes(r.namespace("articles")))
+                    .at("users", |r| article_routes(r.namespace("users")))
+            })
+            .unwrap()
+            .reverse_router()
+            .unwrap();
+
+        assert_eq!(
+            router.routes(),
+            vec![
+                ("api.v1.articles.index", "/api/v1/articles"),
+                ("api.v1.articles.show", "/api/v1/articles/:id"),
+                ("api.v1.users.index", "/api/v1/users"),
+                ("api.v1.users.show", "/api/v1/users/:id"),
+                ("root", "/"),
+            ]
+        );
+    }
+
+    crate::typed_routes! {
+        struct BlogArticle = "blog.article" { id: u64 }
+    }
+
+    #[test]
+    fn should_not_prefix_typed_names_with_namespaces() {
+        let router = root::<()>()
+            .at("blog", |r| {
+                r.namespace("blog").at("articles/:id", |r| {
+                    r.typed_name::<BlogArticle>().get(|_| async { Ok("") })
+                })
+            })
+            .unwrap()
+            .reverse_router()
+            .unwrap();
+
+        assert_eq!(
+            router.resolve_route(&BlogArticle { id: 5 }),
+            Ok("/blog/articles/5".to_string())
+        );
+    }
+
+    crate::typed_routes! {
+        struct ArticleBySlug = "article" { slug: String }
+    }
+
+    #[test]
+    fn should_fail_typed_name_with_different_params() {
+        let error = root::<()>()
+            .at("articles/:id", |r| r.typed_name::<ArticleBySlug>())
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast::<RouteError>().unwrap(),
+            RouteError::TypedRouteParams {
+                name: "article".to_string(),
+                path: "/articles/:id".to_string(),
+                params: vec!["slug".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn should_fail_adding_second_namespace() {
+        let error = root::<()>().namespace("api").namespace("v1").unwrap_err();
+
+        assert_eq!(
+            error.downcast::<RouteError>().unwrap(),
+            RouteError::AlreadyNamespaced {
+                namespace: "api".to_string(),
+                rejected: "v1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn should_name_method_endpoints() {
+        let router = root::<()>()
+            .at("articles", |r| {
+                r.namespace("articles")
+                    .get_named("index", |_| async { Ok("") })
+                    .post_named("create", |_| async { Ok("") })
+            })
+            .unwrap()
+            .reverse_router()
+            .unwrap();
+
+        assert_eq!(
+            router.resolve("articles.create", Params::new()),
+            Ok("/articles".to_string())
+        );
+        assert_eq!(router.method("articles.index"), Some(Method::Get));
+        assert_eq!(router.method("articles.create"), Some(Method::Post));
+    }
+
+    #[test]
+    fn should_report_duplicate_segment_and_method_names() {
+        let error = root::<()>()
+            .at("articles", |r| {
+                r.name("articles")
+                    .post_named("articles", |_| async { Ok("") })
+            })
+            .unwrap()
+            .reverse_router()
+            .unwrap_err();
+
+        assert_eq!(duplicate_names(error).len(), 1);
+    }
+
+    #[test]
+    fn should_describe_routes() {
+        let segment = root::<()>()
+            .get(|_| async { Ok("") })
+            .at("articles", |r| {
+                r.namespace("articles").with(middleware, |r| {
+                    r.name("index")
+                        .get(|_| async { Ok("") })
+                        .post_named("create", |_| async { Ok("") })
+                        .at(":id", |r| r.all(|_| async { Ok("") }))
+                })
+            })
+            .unwrap();
+
+        let routes: Vec<_> = segment
+            .routes()
+            .into_iter()
+            .map(|route| {
+                (
+                    route.path.to_string(),
+                    route.method,
+                    route.name,
+                    route.middleware.len(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            routes,
+            vec![
+                ("/".to_string(), Some(Method::Get), None, 0),
+                (
+                    "/articles".to_string(),
+                    Some(Method::Get),
+                    Some("articles.index".to_string()),
+                    1
+                ),
+                (
+                    "/articles".to_string(),
+                    Some(Method::Post),
+                    Some("articles.create".to_string()),
+                    1
+                ),
+                ("/articles/:id".to_string(), None, None, 1),
+            ]
+        );
+        assert!(segment.routes()[1].middleware[0].contains("middleware"));
+    }
 }
@@ -0,0 +1,173 @@
+//! Human readable listings of the routes in a route tree, as an aligned table of all endpoints or
+//! as a tree that mirrors the nesting of `at` and `with`. Listings can be logged when registering
+//! using `RegisterOptions::log_routes` or compared against snapshots in tests;
+//! ```rust
+//! # use tide::{Request, Result};
+//! # async fn endpoint(_: Request<()>) -> Result {
+//! #     todo!()
+//! # }
+//! use tide_fluent_routes::prelude::*;
+//!
+//! let routes = root::<()>()
+//!     .get(endpoint)
+//!     .at("articles/:id", |route| route.name("article").get(endpoint))
+//!     .expect("Error setting up routes");
+//!
+//! assert_eq!(
+//!     routes.route_table(),
+//!     "METHOD  PATH           NAME     MIDDLEWARE
+//! GET     /
+//! GET     /articles/:id  article
+//! "
+//! );
+//! ```
+
+use crate::routesegment::RouteInfo;
+
+/// Format routes as a table with a METHOD, PATH, NAME and MIDDLEWARE column, catch-all endpoints
+/// are listed with method ANY
+pub fn route_table(routes: &[RouteInfo]) -> String {
+    let header = [
+        "METHOD".to_string(),
+        "PATH".to_string(),
+        "NAME".to_string(),
+        "MIDDLEWARE".to_string(),
+    ];
+
+    let rows: Vec<[String; 4]> = routes
+        .iter()
+        .map(|route| {
+            [
+                method_name(route),
+                route.path.describe(),
+                route.name.clone().unwrap_or_default(),
+                route
+                    .middleware
+                    .iter()
+                    .map(|ware| short_type_name(ware))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ]
+        })
+        .collect();
+
+    let mut widths = [0; 4];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        table.push_str(line.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+fn method_name(route: &RouteInfo) -> String {
+    match route.method {
+        Some(method) => method.to_string(),
+        None => "ANY".to_string(),
+    }
+}
+
+/// Remove the module paths from a type name, `tide::log::LogMiddleware` becomes `LogMiddleware`
+pub(crate) fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut start = 0;
+    let mut chars = name.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            short.truncate(start);
+        } else {
+            short.push(c);
+            if !(c.is_alphanumeric() || c == '_') {
+                start = short.len();
+            }
+        }
+    }
+
+    short
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::test_util::endpoint;
+    use tide::{utils::async_trait, Middleware, Next, Request};
+
+    #[derive(Debug)]
+    struct Auth;
+
+    #[async_trait]
+    impl Middleware<()> for Auth {
+        async fn handle(&self, request: Request<()>, next: Next<'_, ()>) -> tide::Result {
+            Ok(next.run(request).await)
+        }
+    }
+
+    fn routes() -> SubRoute<()> {
+        root()
+            .get(endpoint)
+            .at("articles", |r| {
+                r.namespace("articles").with(Auth, |r| {
+                    r.name("index")
+                        .get(endpoint)
+                        .post_named("create", endpoint)
+                        .at(":id", |r| r.all(endpoint))
+                })
+            })
+            .at("files/*path", |r| r.get(endpoint))
+    }
+
+    #[test]
+    fn should_format_route_table() {
+        assert_eq!(
+            routes().unwrap().route_table(),
+            "METHOD  PATH           NAME             MIDDLEWARE
+GET     /
+GET     /articles      articles.index   Auth
+POST    /articles      articles.create  Auth
+ANY     /articles/:id                   Auth
+GET     /files/*path
+"
+        );
+    }
+
+    #[test]
+    fn should_format_route_tree() {
+        assert_eq!(
+            routes().unwrap().route_tree(),
+            "/
+  GET
+  articles [namespace articles]
+    with Auth [name articles.index]
+      GET
+      POST articles.create
+      :id
+        ANY
+  files/*path
+    GET
+"
+        );
+    }
+
+    #[test]
+    fn should_shorten_type_names() {
+        assert_eq!(short_type_name("tide::log::LogMiddleware"), "LogMiddleware");
+        assert_eq!(
+            short_type_name("app::Wrap<std::sync::Arc<app::auth::Auth>>"),
+            "Wrap<Arc<Auth>>"
+        );
+    }
+}
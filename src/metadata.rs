@@ -0,0 +1,200 @@
+//! Typed metadata for routes. Values are stored by their type, segments inherit the metadata of
+//! their parents and can replace inherited values of the same type. Metadata is part of the route
+//! introspection and is available to middleware and endpoints through the request;
+//! ```rust
+//! use tide::{Request, Result};
+//! use tide_fluent_routes::prelude::*;
+//!
+//! #[derive(Debug)]
+//! struct Scope(&'static str);
+//!
+//! async fn endpoint(request: Request<()>) -> Result {
+//!     let scope = request.route_metadata::<Scope>().map_or("none", |scope| scope.0);
+//!     Ok(scope.into())
+//! }
+//!
+//! let mut server = tide::Server::new();
+//!
+//! server.register(
+//!     root()
+//!         .get(endpoint)
+//!         .at("admin", |route| route
+//!             .meta(Scope("admin"))
+//!             .get(endpoint)
+//!         )
+//! ).expect("Error setting up routes");
+//! ```
+
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::sync::Arc;
+use tide::{utils::async_trait, Middleware, Next, Request};
+
+/// Metadata values of a route by type
+#[derive(Clone, Default)]
+pub struct Metadata {
+    values: HashMap<TypeId, (&'static str, Arc<dyn Any + Send + Sync>)>,
+}
+
+impl Metadata {
+    /// Get the value of type `T`
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|(_, value)| value.downcast_ref())
+    }
+
+    /// The type names of all values, sorted
+    pub fn type_names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.values.values().map(|(name, _)| *name).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Check if there are no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Check if both hold the same values, not just equal ones
+    pub(crate) fn is_same(&self, other: &Metadata) -> bool {
+        self.values.len() == other.values.len()
+            && self.values.iter().all(|(key, (_, value))| {
+                other
+                    .values
+                    .get(key)
+                    .map_or(false, |(_, other)| Arc::ptr_eq(value, other))
+            })
+    }
+
+    pub(crate) fn insert<T: Any + Send + Sync>(&mut self, value: T) {
+        self.values
+            .insert(TypeId::of::<T>(), (type_name::<T>(), Arc::new(value)));
+    }
+
+    /// Combine inherited metadata with the metadata of a segment, values of the segment replace
+    /// inherited values of the same type
+    pub(crate) fn inherit(&self, parent: &Metadata) -> Metadata {
+        let mut values = parent.values.clone();
+        values.extend(self.values.iter().map(|(key, value)| (*key, value.clone())));
+        Metadata { values }
+    }
+}
+
+impl Debug for Metadata {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        formatter.debug_set().entries(self.type_names()).finish()
+    }
+}
+
+/// Middleware that makes the metadata of the route available in the request extensions
+#[derive(Debug)]
+pub(crate) struct MetadataMiddleware(Metadata);
+
+impl MetadataMiddleware {
+    pub(crate) fn new(metadata: Metadata) -> Self {
+        Self(metadata)
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for MetadataMiddleware {
+    async fn handle(&self, mut request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        request.set_ext(self.0.clone());
+        Ok(next.run(request).await)
+    }
+}
+
+/// Extension methods for reading the metadata of the matched route from a request
+pub trait RouteMetadataExt {
+    /// Get the metadata value of type `T` of the route that matched the request
+    fn route_metadata<T: Any>(&self) -> Option<&T>;
+}
+
+impl<State> RouteMetadataExt for Request<State> {
+    fn route_metadata<T: Any>(&self) -> Option<&T> {
+        self.ext::<Metadata>()
+            .and_then(|metadata| metadata.get::<T>())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::test_util::{call, endpoint};
+    use tide::{Response, StatusCode};
+
+    #[derive(Debug, PartialEq)]
+    struct Scope(&'static str);
+
+    #[derive(Debug, PartialEq)]
+    struct Team(&'static str);
+
+    #[test]
+    fn should_inherit_metadata() {
+        let routes = root::<()>()
+            .meta(Team("web"))
+            .get(endpoint)
+            .at("admin", |r| {
+                r.meta(Scope("admin"))
+                    .get(endpoint)
+                    .at("billing", |r| r.meta(Team("billing")).get(endpoint))
+            })
+            .unwrap()
+            .routes();
+
+        let metadata: Vec<_> = routes
+            .iter()
+            .map(|route| (route.metadata.get::<Scope>(), route.metadata.get::<Team>()))
+            .collect();
+
+        assert_eq!(
+            metadata,
+            vec![
+                (None, Some(&Team("web"))),
+                (Some(&Scope("admin")), Some(&Team("web"))),
+                (Some(&Scope("admin")), Some(&Team("billing"))),
+            ]
+        );
+    }
+
+    /// Middleware that refuses requests for routes without a scope
+    #[derive(Debug)]
+    struct RequireScope;
+
+    #[async_trait]
+    impl Middleware<()> for RequireScope {
+        async fn handle(&self, request: Request<()>, next: Next<'_, ()>) -> tide::Result {
+            match request.route_metadata::<Scope>() {
+                Some(_) => Ok(next.run(request).await),
+                None => Ok(Response::new(StatusCode::Forbidden)),
+            }
+        }
+    }
+
+    #[async_std::test]
+    async fn should_read_metadata_from_request() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().with(RequireScope, |r| {
+                r.get(endpoint).at("admin", |r| {
+                    r.meta(Scope("admin"))
+                        .get(|request: Request<()>| async move {
+                            Ok(request.route_metadata::<Scope>().unwrap().0)
+                        })
+                })
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/").await.0,
+            StatusCode::Forbidden
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/admin").await,
+            (StatusCode::Ok, "admin".to_string())
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Synthesized endpoints for the methods a path has no endpoint for, see
+//! `RegisterOptions::synthesize_methods`. Every path without a catch-all endpoint answers OPTIONS
+//! with the allowed methods, answers HEAD using its GET endpoint and responds to all other methods
+//! with a 405 Method Not Allowed. The server leaves the body out of HEAD responses and keeps the
+//! headers of the GET response. Synthesized endpoints run the middleware that all endpoints of the
+//! path share, middleware added for only some of the methods doesn't apply to them.
+
+use crate::constraint::ConstrainedRoute;
+use crate::path::Path;
+use crate::util::{BoxedEndpoint, SharedEndpoint};
+use tide::http::headers::ALLOW;
+use tide::http::Method;
+use tide::{utils::async_trait, Endpoint, Request, Response, StatusCode};
+
+/// Add OPTIONS, HEAD and method not allowed endpoints to every path that has no catch-all
+/// endpoint, explicitly defined OPTIONS and HEAD endpoints are kept. The method not allowed
+/// endpoints are fallbacks, constrained sibling routes with an endpoint for the method win.
+pub(crate) fn synthesize<State: Clone + Send + Sync + 'static>(
+    routes: Vec<ConstrainedRoute<State>>,
+) -> Vec<ConstrainedRoute<State>> {
+    let mut paths: Vec<Path> = Vec::new();
+    for route in &routes {
+        if !paths.contains(&route.path) {
+            paths.push(route.path.clone());
+        }
+    }
+
+    let mut routes: Vec<Option<ConstrainedRoute<State>>> = routes.into_iter().map(Some).collect();
+    let mut synthesized = Vec::new();
+
+    for path in paths {
+        let methods: Vec<Option<Method>> = routes
+            .iter()
+            .flatten()
+            .filter(|route| route.path == path)
+            .map(|route| route.method)
+            .collect();
+
+        if methods.contains(&None) {
+            continue;
+        }
+
+        // Synthesized endpoints only run the middleware that all endpoints of the path share
+        let mut path_routes = routes.iter().flatten().filter(|route| route.path == path);
+        let mut middleware = path_routes
+            .next()
+            .map(|route| route.middleware.clone())
+            .unwrap_or_default();
+        for route in path_routes {
+            let shared = middleware
+                .iter()
+                .zip(&route.middleware)
+                .take_while(|(ware, other)| ware.ptr_eq(other))
+                .count();
+            middleware.truncate(shared);
+        }
+
+        let has = |method| methods.contains(&Some(method));
+        let mut allowed: Vec<Method> = methods.iter().flatten().copied().collect();
+
+        if has(Method::Get) && !has(Method::Head) {
+            let index = routes
+                .iter()
+                .position(|route| match route {
+                    Some(route) => route.path == path && route.method == Some(Method::Get),
+                    None => false,
+                })
+                .expect("path has a GET endpoint");
+
+            let get = routes[index].take().expect("route is not taken yet");
+            let endpoint = SharedEndpoint::new(get.endpoint);
+
+            synthesized.push(ConstrainedRoute {
+                path: path.clone(),
+                method: Some(Method::Head),
+                middleware: get.middleware.clone(),
+                endpoint: BoxedEndpoint::new(endpoint.clone()),
+                fallback: false,
+            });
+            routes[index] = Some(ConstrainedRoute {
+                endpoint: BoxedEndpoint::new(endpoint),
+                ..get
+            });
+            allowed.push(Method::Head);
+        }
+
+        if !has(Method::Options) {
+            allowed.push(Method::Options);
+        }
+
+        let mut allowed: Vec<String> = allowed.iter().map(Method::to_string).collect();
+        allowed.sort();
+        allowed.dedup();
+        let allow = allowed.join(", ");
+
+        if !has(Method::Options) {
+            synthesized.push(ConstrainedRoute {
+                path: path.clone(),
+                method: Some(Method::Options),
+                middleware: middleware.clone(),
+                endpoint: BoxedEndpoint::new(OptionsEndpoint(allow.clone())),
+                fallback: false,
+            });
+        }
+
+        synthesized.push(ConstrainedRoute {
+            path,
+            method: None,
+            middleware,
+            endpoint: BoxedEndpoint::new(MethodNotAllowedEndpoint(allow)),
+            fallback: true,
+        });
+    }
+
+    routes.into_iter().flatten().chain(synthesized).collect()
+}
+
+/// Responds to OPTIONS with the allowed methods
+#[derive(Debug)]
+struct OptionsEndpoint(String);
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for OptionsEndpoint {
+    async fn call(&self, _req: Request<State>) -> tide::Result {
+        let mut response = Response::new(StatusCode::NoContent);
+        response.insert_header(ALLOW, self.0.as_str());
+        Ok(response)
+    }
+}
+
+/// Responds with a 405 Method Not Allowed and the allowed methods
+#[derive(Debug)]
+struct MethodNotAllowedEndpoint(String);
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for MethodNotAllowedEndpoint {
+    async fn call(&self, _req: Request<State>) -> tide::Result {
+        let mut response = Response::new(StatusCode::MethodNotAllowed);
+        response.insert_header(ALLOW, self.0.as_str());
+        Ok(response)
+    }
+}
@@ -0,0 +1,527 @@
+//! Generates an OpenAPI 3 document from a route tree. Every endpoint for a single method becomes an
+//! operation, route names are used as operation ids and `document` adds a summary, tags and schemas
+//! to the endpoint that was added last;
+//! ```rust
+//! # use tide::{Request, Result};
+//! # async fn endpoint(_: Request<()>) -> Result {
+//! #     todo!()
+//! # }
+//! use tide_fluent_routes::openapi::{Info, Operation};
+//! use tide_fluent_routes::prelude::*;
+//!
+//! let routes = root::<()>()
+//!     .at("articles/:id", |route| route
+//!         .get_named("article", endpoint)
+//!         .document(Operation::new().summary("Get an article").tag("articles"))
+//!     )
+//!     .at("openapi.json", |route| route.serve_openapi(Info::new("Articles", "1.0")))
+//!     .expect("Error setting up routes");
+//!
+//! let document = routes.openapi(&Info::new("Articles", "1.0"));
+//! assert_eq!(
+//!     document["paths"]["/articles/{id}"]["get"]["operationId"],
+//!     "article"
+//! );
+//! ```
+//! Catch-all endpoints are left out of the document because OpenAPI has no way to describe them,
+//! and so is the endpoint added with `serve_openapi`. Paths that only differ in parameter names
+//! and constraints are the same path for OpenAPI, they are described as one path with the parameter
+//! names of the route that was added first. The document is generated as JSON, YAML output is out
+//! of scope but the returned value can be serialized with any serde format.
+
+use crate::constraint::Constraint;
+use crate::path::Segment;
+use crate::routesegment::RouteInfo;
+use crate::{HashMap, Result};
+use serde_json::{json, Map, Value};
+use tide::http::Method;
+use tide::{utils::async_trait, Body, Endpoint, Request, StatusCode};
+
+/// The title and version of the API, these are required in an OpenAPI document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    title: String,
+    version: String,
+}
+
+impl Info {
+    /// Construct api info from a title and a version
+    pub fn new(title: &str, version: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            version: version.to_string(),
+        }
+    }
+}
+
+/// Documentation for a single endpoint, schemas are JSON schemas for JSON request and response
+/// bodies
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Operation {
+    summary: Option<String>,
+    description: Option<String>,
+    tags: Vec<String>,
+    request_schema: Option<Value>,
+    responses: Vec<(StatusCode, String, Option<Value>)>,
+    excluded: bool,
+}
+
+impl Operation {
+    /// Construct empty endpoint documentation
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a short summary of what the endpoint does
+    pub fn summary(mut self, summary: &str) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    /// Set a longer description of the endpoint
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Add a tag for grouping endpoints
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Set the schema of the JSON request body
+    pub fn request_schema(mut self, schema: Value) -> Self {
+        self.request_schema = Some(schema);
+        self
+    }
+
+    /// Add a response without a body
+    pub fn response(mut self, status: StatusCode, description: &str) -> Self {
+        self.responses.push((status, description.to_string(), None));
+        self
+    }
+
+    /// Add a response with a JSON body
+    pub fn json_response(mut self, status: StatusCode, description: &str, schema: Value) -> Self {
+        self.responses
+            .push((status, description.to_string(), Some(schema)));
+        self
+    }
+
+    /// Leave the endpoint out of the document
+    pub fn exclude(mut self) -> Self {
+        self.excluded = true;
+        self
+    }
+
+    fn to_json(&self, operation_id: Option<&str>) -> Value {
+        let mut operation = Map::new();
+
+        if let Some(operation_id) = operation_id {
+            operation.insert("operationId".to_string(), json!(operation_id));
+        }
+        if let Some(summary) = &self.summary {
+            operation.insert("summary".to_string(), json!(summary));
+        }
+        if let Some(description) = &self.description {
+            operation.insert("description".to_string(), json!(description));
+        }
+        if !self.tags.is_empty() {
+            operation.insert("tags".to_string(), json!(self.tags));
+        }
+        if let Some(schema) = &self.request_schema {
+            operation.insert(
+                "requestBody".to_string(),
+                json!({"content": {"application/json": {"schema": schema}}}),
+            );
+        }
+
+        let mut responses = Map::new();
+        for (status, description, schema) in &self.responses {
+            let mut response = json!({ "description": description });
+            if let Some(schema) = schema {
+                response["content"] = json!({"application/json": {"schema": schema}});
+            }
+            responses.insert((*status as u16).to_string(), response);
+        }
+        if responses.is_empty() {
+            responses.insert("default".to_string(), json!({"description": "Response"}));
+        }
+        operation.insert("responses".to_string(), Value::Object(responses));
+
+        Value::Object(operation)
+    }
+}
+
+/// Generate an OpenAPI 3 document for routes, see `RouteSegment::openapi`. An endpoint that is
+/// followed by another endpoint for the same path and method is overridden and left out.
+pub fn openapi_document(info: &Info, routes: &[RouteInfo]) -> Value {
+    let routes: Vec<(&RouteInfo, &str)> = routes
+        .iter()
+        .enumerate()
+        .filter(|(index, route)| {
+            !routes[index + 1..]
+                .iter()
+                .any(|later| later.path == route.path && later.method == route.method)
+        })
+        .filter(|(_, route)| !route.operation.as_ref().map_or(false, |op| op.excluded))
+        .filter_map(|(_, route)| Some((route, operation_method(route.method?)?)))
+        .collect();
+
+    // Operation ids have to be unique, names that are shared by several methods get the method
+    // appended and ids that are still taken get a number appended
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for (route, _) in &routes {
+        if let Some(name) = &route.name {
+            *name_counts.entry(name).or_insert(0) += 1;
+        }
+    }
+    let mut operation_ids: Vec<String> = Vec::new();
+
+    // Routes with the same shape are the same path for OpenAPI, they are grouped under the path
+    // of the first route and the first route for a method is documented
+    let mut groups: Vec<(String, Vec<(&RouteInfo, &str)>)> = Vec::new();
+    for (route, method) in routes {
+        let shape = route.path.shape();
+        match groups.iter_mut().find(|(existing, _)| *existing == shape) {
+            Some((_, group)) => group.push((route, method)),
+            None => groups.push((shape, vec![(route, method)])),
+        }
+    }
+
+    let mut paths = Map::new();
+    for (_, group) in groups {
+        let first = group[0].0;
+        let template = first.path.join(
+            &first
+                .path
+                .iter()
+                .map(|segment| match segment.param() {
+                    Some(param) => format!("{{{}}}", param),
+                    None => segment.to_string(),
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        // Values that fail the constraints of one route can still match another route
+        let constrained = group.iter().all(|(route, _)| route.path == first.path);
+        let mut path_item = path_item(first, constrained);
+
+        for (route, method) in group {
+            if !path_item[method].is_null() {
+                continue;
+            }
+
+            let operation_id = route.name.as_deref().map(|name| {
+                let candidate = match name_counts[name] {
+                    1 => name.to_string(),
+                    _ => format!("{}.{}", name, method),
+                };
+                let mut operation_id = candidate.clone();
+                let mut number = 1;
+                while operation_ids.contains(&operation_id) {
+                    number += 1;
+                    operation_id = format!("{}.{}", candidate, number);
+                }
+                operation_ids.push(operation_id.clone());
+                operation_id
+            });
+            path_item[method] = route
+                .operation
+                .clone()
+                .unwrap_or_default()
+                .to_json(operation_id.as_deref());
+        }
+
+        paths.insert(template, path_item);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {"title": info.title, "version": info.version},
+        "paths": paths,
+    })
+}
+
+/// The name of an operation for a method in a path item, methods that OpenAPI can not describe
+/// return `None`
+fn operation_method(method: Method) -> Option<&'static str> {
+    match method {
+        Method::Get => Some("get"),
+        Method::Put => Some("put"),
+        Method::Post => Some("post"),
+        Method::Delete => Some("delete"),
+        Method::Options => Some("options"),
+        Method::Head => Some("head"),
+        Method::Patch => Some("patch"),
+        Method::Trace => Some("trace"),
+        _ => None,
+    }
+}
+
+/// A path item with the path parameters of the route, the constraints of the parameters are
+/// added to their schemas when `constrained` is set
+fn path_item(route: &RouteInfo, constrained: bool) -> Value {
+    let parameters: Vec<Value> = route
+        .path
+        .iter()
+        .filter_map(|segment| {
+            let name = segment.param()?;
+            let mut schema = json!({"type": "string"});
+
+            if let Segment::Param { constraints, .. } = segment {
+                let mut schemas: Vec<Value> = constraints.iter().map(constraint_schema).collect();
+                if !constrained {
+                    schemas.clear();
+                }
+
+                // A schema can only have one pattern, more constraints have to match all of them
+                match schemas.len() {
+                    0 => {}
+                    1 => {
+                        if let (Value::Object(schema), Value::Object(constraint)) =
+                            (&mut schema, schemas.remove(0))
+                        {
+                            schema.extend(constraint);
+                        }
+                    }
+                    _ => schema["allOf"] = json!(schemas),
+                }
+            }
+
+            Some(json!({"name": name, "in": "path", "required": true, "schema": schema}))
+        })
+        .collect();
+
+    if parameters.is_empty() {
+        json!({})
+    } else {
+        json!({ "parameters": parameters })
+    }
+}
+
+/// The part of a parameter schema that describes a constraint
+fn constraint_schema(constraint: &Constraint) -> Value {
+    match constraint {
+        Constraint::Numeric => json!({"pattern": "^[0-9]+$"}),
+        Constraint::Alphanumeric => json!({"pattern": "^[a-zA-Z0-9]+$"}),
+        Constraint::OneOf(values) => json!({ "enum": values }),
+        Constraint::Pattern(regex) => json!({"pattern": regex.as_str()}),
+    }
+}
+
+/// Serves a generated OpenAPI document as json
+#[derive(Debug)]
+pub(crate) struct OpenApiEndpoint(String);
+
+impl OpenApiEndpoint {
+    pub(crate) fn new(info: &Info, routes: &[RouteInfo]) -> Result<Self> {
+        Ok(Self(serde_json::to_string(&openapi_document(
+            info, routes,
+        ))?))
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for OpenApiEndpoint {
+    async fn call(&self, _req: Request<State>) -> tide::Result {
+        let mut body = Body::from_string(self.0.clone());
+        body.set_mime(tide::http::mime::JSON);
+        Ok(body.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::test_util::endpoint;
+
+    #[test]
+    fn should_generate_document() {
+        let document = root::<()>()
+            .at("articles", |r| {
+                r.get_named("articles.index", endpoint)
+                    .document(
+                        Operation::new()
+                            .summary("List articles")
+                            .tag("articles")
+                            .json_response(
+                                StatusCode::Ok,
+                                "The articles",
+                                json!({"type": "array"}),
+                            ),
+                    )
+                    .post_named("articles.create", endpoint)
+                    .document(
+                        Operation::new()
+                            .request_schema(json!({"type": "object"}))
+                            .response(StatusCode::Created, "Created"),
+                    )
+                    .at(r":id(\d+)", |r| {
+                        r.name("article").get(endpoint).put(endpoint)
+                    })
+            })
+            .at("files/*path", |r| r.all(endpoint))
+            .unwrap()
+            .openapi(&Info::new("Articles", "1.0"));
+
+        assert_eq!(
+            document,
+            json!({
+                "openapi": "3.0.3",
+                "info": {"title": "Articles", "version": "1.0"},
+                "paths": {
+                    "/articles": {
+                        "get": {
+                            "operationId": "articles.index",
+                            "summary": "List articles",
+                            "tags": ["articles"],
+                            "responses": {
+                                "200": {
+                                    "description": "The articles",
+                                    "content": {"application/json": {"schema": {"type": "array"}}}
+                                }
+                            }
+                        },
+                        "post": {
+                            "operationId": "articles.create",
+                            "requestBody": {
+                                "content": {"application/json": {"schema": {"type": "object"}}}
+                            },
+                            "responses": {"201": {"description": "Created"}}
+                        }
+                    },
+                    "/articles/{id}": {
+                        "parameters": [{
+                            "name": "id",
+                            "in": "path",
+                            "required": true,
+                            "schema": {"type": "string", "pattern": r"^(?:\d+)$"}
+                        }],
+                        "get": {
+                            "operationId": "article.get",
+                            "responses": {"default": {"description": "Response"}}
+                        },
+                        "put": {
+                            "operationId": "article.put",
+                            "responses": {"default": {"description": "Response"}}
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn should_leave_out_overridden_and_openapi_endpoints() {
+        let document = root::<()>()
+            .at("articles", |r| {
+                r.get_named("old", endpoint).get_named("articles", endpoint)
+            })
+            .at("openapi.json", |r| {
+                r.serve_openapi(Info::new("Articles", "1.0"))
+            })
+            .unwrap()
+            .openapi(&Info::new("Articles", "1.0"));
+
+        assert_eq!(
+            document["paths"],
+            json!({
+                "/articles": {
+                    "get": {
+                        "operationId": "articles",
+                        "responses": {"default": {"description": "Response"}}
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn should_merge_paths_with_same_shape() {
+        let document = root::<()>()
+            .at("articles", |r| {
+                r.at_matching(":id", Constraint::Numeric, |r| {
+                    r.get_named("article", endpoint)
+                })
+                .at(":slug", |r| {
+                    r.get_named("article_by_slug", endpoint)
+                        .delete_named("delete_article", endpoint)
+                })
+            })
+            .unwrap()
+            .openapi(&Info::new("Articles", "1.0"));
+
+        let paths = document["paths"].as_object().unwrap();
+        assert_eq!(paths.keys().collect::<Vec<_>>(), vec!["/articles/{id}"]);
+        assert_eq!(
+            paths["/articles/{id}"]["parameters"],
+            json!([{"name": "id", "in": "path", "required": true, "schema": {"type": "string"}}])
+        );
+        assert_eq!(paths["/articles/{id}"]["get"]["operationId"], "article");
+        assert_eq!(
+            paths["/articles/{id}"]["delete"]["operationId"],
+            "delete_article"
+        );
+    }
+
+    #[test]
+    fn should_name_unnamed_wildcard() {
+        let document = root::<()>()
+            .at("files/*", |r| r.get(endpoint))
+            .unwrap()
+            .openapi(&Info::new("Files", "1.0"));
+
+        assert_eq!(
+            document["paths"]["/files/{path}"]["parameters"][0]["name"],
+            "path"
+        );
+    }
+
+    #[test]
+    fn should_combine_constraints_of_a_parameter() {
+        let document = root::<()>()
+            .at_matching(
+                r":id(\d+)",
+                Constraint::OneOf(vec!["1".to_string(), "2".to_string()]),
+                |r| r.get(endpoint),
+            )
+            .unwrap()
+            .openapi(&Info::new("Articles", "1.0"));
+
+        assert_eq!(
+            document["paths"]["/{id}"]["parameters"][0]["schema"],
+            json!({
+                "type": "string",
+                "allOf": [{"pattern": r"^(?:\d+)$"}, {"enum": ["1", "2"]}]
+            })
+        );
+    }
+
+    #[test]
+    fn should_make_operation_ids_unique() {
+        let document = root::<()>()
+            .at("articles/:id", |r| {
+                r.name("article").get(endpoint).put(endpoint)
+            })
+            .at("legacy/:id", |r| r.get_named("article.get", endpoint))
+            .unwrap()
+            .openapi(&Info::new("Articles", "1.0"));
+
+        assert_eq!(
+            document["paths"]["/articles/{id}"]["get"]["operationId"],
+            "article.get"
+        );
+        assert_eq!(
+            document["paths"]["/legacy/{id}"]["get"]["operationId"],
+            "article.get.2"
+        );
+    }
+
+    #[test]
+    fn should_fail_documenting_without_endpoint() {
+        assert!(root::<()>().document(Operation::new()).is_err());
+    }
+}
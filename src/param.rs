@@ -0,0 +1,186 @@
+//! Typed path parameters, parameters are parsed once by middleware before the endpoints are called
+//! and endpoints read the parsed values from the request;
+//! ```rust
+//! use tide::{Request, Result};
+//! use tide_fluent_routes::prelude::*;
+//!
+//! async fn article(request: Request<()>) -> Result {
+//!     let id: &u64 = request.typed_param("id")?;
+//!     Ok(format!("article {}", id).into())
+//! }
+//!
+//! let mut server = tide::Server::new();
+//!
+//! server.register(
+//!     root()
+//!         .at("articles", |route| route
+//!             .param::<u64>("id", |route| route
+//!                 .get(article)
+//!             )
+//!         )
+//! ).expect("Error setting up routes");
+//! ```
+
+use percent_encoding::percent_decode_str;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use tide::{utils::async_trait, Middleware, Next, Request, Response, StatusCode};
+
+/// Parsed path parameters, stored in the request extensions
+#[derive(Default)]
+struct TypedParams(HashMap<String, Box<dyn Any + Send + Sync>>);
+
+/// Middleware that parses a path parameter and responds with 400 Bad Request when that fails
+pub(crate) struct ParseParam<T> {
+    name: String,
+    param_type: PhantomData<fn() -> T>,
+}
+
+impl<T> ParseParam<T> {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            param_type: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for ParseParam<T> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        formatter
+            .debug_struct("ParseParam")
+            .field("name", &self.name)
+            .field("type", &std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<State, T> Middleware<State> for ParseParam<T>
+where
+    State: Clone + Send + Sync + 'static,
+    T: FromStr + Send + Sync + 'static,
+{
+    async fn handle(&self, mut request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let parsed = percent_decode_str(request.param(&self.name)?)
+            .decode_utf8()
+            .ok()
+            .and_then(|value| value.parse::<T>().ok());
+
+        match parsed {
+            Some(value) => {
+                if request.ext::<TypedParams>().is_none() {
+                    request.set_ext(TypedParams::default());
+                }
+                if let Some(params) = request.ext_mut::<TypedParams>() {
+                    params.0.insert(self.name.clone(), Box::new(value));
+                }
+
+                Ok(next.run(request).await)
+            }
+            None => Ok(Response::builder(StatusCode::BadRequest)
+                .body(format!("Invalid value for parameter {}", self.name))
+                .build()),
+        }
+    }
+}
+
+/// Extension methods for reading typed path parameters from a request
+pub trait TypedParamExt {
+    /// Get the parsed value of a path parameter that was declared using `RouteBuilderExt::param`.
+    /// Fails when the parameter was not declared or was declared with a different type.
+    fn typed_param<T: Send + Sync + 'static>(&self, name: &str) -> tide::Result<&T>;
+}
+
+impl<State> TypedParamExt for Request<State> {
+    fn typed_param<T: Send + Sync + 'static>(&self, name: &str) -> tide::Result<&T> {
+        self.ext::<TypedParams>()
+            .and_then(|params| params.0.get(name))
+            .and_then(|value| value.downcast_ref::<T>())
+            .ok_or_else(|| {
+                tide::Error::from_str(
+                    StatusCode::InternalServerError,
+                    format!(
+                        "Parameter {} of type {} is not declared",
+                        name,
+                        std::any::type_name::<T>()
+                    ),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use crate::test_util::call;
+    use tide::{Request, StatusCode};
+
+    fn server() -> tide::Server<()> {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("articles", |r| {
+                r.param::<u64>("id", |r| {
+                    r.get(|req: Request<()>| async move {
+                        let id: u64 = *req.typed_param("id")?;
+                        Ok(format!("article {}", id + 1))
+                    })
+                    .at("tags", |r| {
+                        r.param::<String>("tag", |r| {
+                            r.get(|req: Request<()>| async move {
+                                let id: &u64 = req.typed_param("id")?;
+                                let tag: &String = req.typed_param("tag")?;
+                                Ok(format!("article {} tag {}", id, tag))
+                            })
+                        })
+                    })
+                    .at("wrong", |r| {
+                        r.get(|req: Request<()>| async move {
+                            let id: &i32 = req.typed_param("id")?;
+                            Ok(id.to_string())
+                        })
+                    })
+                })
+            }))
+            .unwrap();
+        server
+    }
+
+    #[async_std::test]
+    async fn should_parse_typed_param() {
+        assert_eq!(
+            call(&server(), Method::Get, "/articles/41").await,
+            (StatusCode::Ok, "article 42".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_keep_params_of_parent_segments() {
+        assert_eq!(
+            call(&server(), Method::Get, "/articles/41/tags/a%20b").await,
+            (StatusCode::Ok, "article 41 tag a b".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_respond_bad_request_for_invalid_param() {
+        assert_eq!(
+            call(&server(), Method::Get, "/articles/forty-one").await,
+            (
+                StatusCode::BadRequest,
+                "Invalid value for parameter id".to_string()
+            )
+        );
+    }
+
+    #[async_std::test]
+    async fn should_fail_reading_param_with_wrong_type() {
+        assert_eq!(
+            call(&server(), Method::Get, "/articles/41/wrong").await.0,
+            StatusCode::InternalServerError
+        );
+    }
+}
@@ -1,29 +1,472 @@
-/// Newtype for collecting path segments into a path
+//! Path patterns for routes. A path is parsed into static, parameter and wildcard segments when it
+//! is added to the route tree, parameters can carry constraints on their values;
+//! ```rust
+//! use tide_fluent_routes::path::{Path, Segment};
+//!
+//! let path = Path::parse(r"/articles/:id(\d+)/*rest").unwrap();
+//!
+//! assert_eq!(path.to_string(), "/articles/:id/*rest");
+//! assert_eq!(path.describe(), r"/articles/:id(\d+)/*rest");
+//! assert_eq!(path.params(), vec!["id", "rest"]);
+//! assert!(matches!(path.iter().next(), Some(Segment::Static(name)) if name == "articles"));
+//! assert!(path.matches("/articles/12/comments/3"));
+//! assert!(!path.matches("/articles/twelve/comments/3"));
+//! ```
+
+use crate::constraint::Constraint;
+use crate::error::RouteError;
+use crate::Result;
+use percent_encoding::percent_decode_str;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::slice::Iter;
+use std::str::FromStr;
+
+/// A parsed path pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    absolute: bool,
+    segments: Vec<Segment>,
+    trailing_slash: bool,
+}
+
+/// A single segment of a path pattern
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) struct Path(String);
+pub enum Segment {
+    /// A segment that only matches its own text
+    Static(String),
+
+    /// A `:name` segment that matches a single segment with a value that satisfies all constraints
+    Param {
+        /// The name of the parameter
+        name: String,
+
+        /// Constraints on the value of the parameter
+        constraints: Vec<Constraint>,
+    },
+
+    /// A `*name` segment that matches the rest of the path, a `*` without a name is named `path`
+    Wildcard(String),
+}
+
+impl Segment {
+    /// The name of the parameter for param and wildcard segments
+    pub fn param(&self) -> Option<&str> {
+        match self {
+            Segment::Static(_) => None,
+            Segment::Param { name, .. } | Segment::Wildcard(name) => Some(name),
+        }
+    }
+
+    /// Check if a request segment can match both segments, wildcards are handled by the path
+    fn overlaps(&self, other: &Segment) -> bool {
+        match (self, other) {
+            (Segment::Static(value), Segment::Static(other)) => value == other,
+            (Segment::Static(value), Segment::Param { constraints, .. })
+            | (Segment::Param { constraints, .. }, Segment::Static(value)) => constraints
+                .iter()
+                .all(|constraint| constraint.matches(value)),
+            _ => true,
+        }
+    }
+
+    /// Rank segments from wildcards to static segments, more specific segments rank higher
+    fn specificity(&self) -> u8 {
+        match self {
+            Segment::Wildcard(_) => 0,
+            Segment::Param { constraints, .. } if constraints.is_empty() => 1,
+            Segment::Param { .. } => 2,
+            Segment::Static(_) => 3,
+        }
+    }
+
+    /// The segment with the constraints of a param written inline
+    fn describe(&self) -> String {
+        match self {
+            Segment::Param { name, constraints } => {
+                let constraints: String = constraints
+                    .iter()
+                    .map(|constraint| format!("({})", constraint.inline()))
+                    .collect();
+                format!(":{}{}", name, constraints)
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Segment::Static(value) => formatter.write_str(value),
+            Segment::Param { name, .. } => write!(formatter, ":{}", name),
+            Segment::Wildcard(name) => write!(formatter, "*{}", name),
+        }
+    }
+}
 
 impl Path {
-    /// Create new path from a prefix
-    pub(crate) fn prefix(value: &str) -> Self {
-        Path(value.to_string())
+    /// The root path `/`
+    pub fn root() -> Self {
+        Path {
+            absolute: true,
+            segments: Vec::new(),
+            trailing_slash: false,
+        }
     }
 
-    pub(crate) fn append(self, segment: &str) -> Path {
-        if self.0.is_empty() {
-            Path(segment.to_string())
-        } else {
-            let mut path = self.0.trim_end_matches('/').to_string();
+    /// Parse a path pattern, parameters can have inline constraints like `:id(\d+)`
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let empty = Path {
+            absolute: false,
+            segments: Vec::new(),
+            trailing_slash: false,
+        };
+
+        empty.append(pattern)
+    }
+
+    /// Append a fragment to the path, fails when the resulting path is not a valid pattern
+    pub fn append(mut self, fragment: &str) -> Result<Path> {
+        let segments = split_segments(fragment)?;
+
+        if self.segments.is_empty() && !self.absolute {
+            self.absolute = fragment.starts_with('/');
+        }
+        self.trailing_slash = segments.is_empty() || fragment.ends_with('/');
+        self.segments.extend(segments);
+
+        self.validate().map_err(|message| RouteError::InvalidPath {
+            fragment: fragment.to_string(),
+            message,
+        })?;
+
+        Ok(self)
+    }
+
+    /// Constrain the last parameter in the path
+    pub(crate) fn constrain(mut self, constraint: Constraint) -> Result<Path> {
+        let path = self.to_string();
+
+        match self
+            .segments
+            .iter_mut()
+            .rev()
+            .find_map(|segment| match segment {
+                Segment::Param { constraints, .. } => Some(constraints),
+                _ => None,
+            }) {
+            Some(constraints) => constraints.push(constraint),
+            None => {
+                return Err(RouteError::InvalidConstraint {
+                    path,
+                    message: "path has no parameter to constrain".to_string(),
+                }
+                .into())
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Iterate over the segments of the path
+    pub fn iter(&self) -> Iter<'_, Segment> {
+        self.segments.iter()
+    }
+
+    /// The names of the param and wildcard segments in the path
+    pub fn params(&self) -> Vec<&str> {
+        self.segments.iter().filter_map(Segment::param).collect()
+    }
+
+    /// Check if the path only has static segments
+    pub fn is_static(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Static(_)))
+    }
+
+    /// The path with the constraints of its params written inline like `:id(\d+)`, used in error
+    /// messages and route listings. Displaying a path leaves out the constraints, tide only
+    /// matches the pattern without them.
+    pub fn describe(&self) -> String {
+        let segments: Vec<String> = self.segments.iter().map(Segment::describe).collect();
+        self.join(&segments)
+    }
+
+    /// Check if any of the params in the path have constraints
+    pub(crate) fn is_constrained(&self) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Param { constraints, .. } => !constraints.is_empty(),
+            _ => false,
+        })
+    }
+
+    /// Check if a request path matches this path, param values are decoded before they are
+    /// checked against constraints. Like tide a trailing slash only matches a path that has one,
+    /// unless the path ends in a wildcard.
+    pub fn matches(&self, path: &str) -> bool {
+        let mut values = path.split('/').filter(|value| !value.is_empty());
+
+        for segment in &self.segments {
+            let value = match values.next() {
+                Some(value) => value,
+                None => return false,
+            };
+
+            match segment {
+                Segment::Static(expected) if expected != value => return false,
+                Segment::Static(_) => {}
+                Segment::Param { constraints, .. } => {
+                    let satisfied =
+                        percent_decode_str(value)
+                            .decode_utf8()
+                            .map_or(false, |value| {
+                                constraints
+                                    .iter()
+                                    .all(|constraint| constraint.matches(&value))
+                            });
+                    if !satisfied {
+                        return false;
+                    }
+                }
+                Segment::Wildcard(_) => return true,
+            }
+        }
+
+        values.next().is_none()
+            && (self.segments.is_empty() || path.ends_with('/') == self.trailing_slash)
+    }
+
+    /// Check if there are request paths that match both paths
+    pub fn overlaps(&self, other: &Path) -> bool {
+        let mut segments = self.segments.iter();
+        let mut others = other.segments.iter();
+
+        loop {
+            match (segments.next(), others.next()) {
+                (None, None) => {
+                    return self.segments.is_empty() || self.trailing_slash == other.trailing_slash
+                }
+                (Some(Segment::Wildcard(_)), Some(_)) | (Some(_), Some(Segment::Wildcard(_))) => {
+                    return true
+                }
+                (Some(segment), Some(other)) if segment.overlaps(other) => {}
+                _ => return false,
+            }
+        }
+    }
+
+    /// Compare how specific two paths are segment by segment, static segments are more specific
+    /// than constrained params, params are more specific than wildcards. When one path is a prefix
+    /// of the other the longer path is more specific.
+    pub fn cmp_specificity(&self, other: &Path) -> Ordering {
+        self.segments
+            .iter()
+            .zip(other.segments.iter())
+            .map(|(segment, other)| segment.specificity().cmp(&other.specificity()))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| self.segments.len().cmp(&other.segments.len()))
+    }
+
+    /// Join values for each of the segments into a path with the same leading and trailing slashes
+    /// as this path
+    pub(crate) fn join<T: Display>(&self, values: &[T]) -> String {
+        let mut path = if self.absolute { "/" } else { "" }.to_string();
+
+        let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        path.push_str(&values.join("/"));
+
+        if self.trailing_slash && !values.is_empty() {
             path.push('/');
-            path.push_str(segment.trim_start_matches('/'));
-            Path(path)
+        }
+        path
+    }
+
+    /// The same path with the trailing slash added or removed, the root and paths that end in a
+    /// wildcard already match both forms
+    pub(crate) fn toggle_trailing_slash(&self) -> Option<Path> {
+        match self.segments.last() {
+            None | Some(Segment::Wildcard(_)) => None,
+            Some(_) => Some(Path {
+                trailing_slash: !self.trailing_slash,
+                ..self.clone()
+            }),
+        }
+    }
+
+    /// The path with all parameter names removed, paths with the same shape are the same route
+    /// for tide
+    pub(crate) fn shape(&self) -> String {
+        self.rename_params(|_| String::new()).to_string()
+    }
+
+    /// The path with generated parameter names, used to register routes that are dispatched by
+    /// the route tree so the params of the dispatching route don't clash with those of the routes
+    pub(crate) fn dispatch_pattern(&self) -> String {
+        self.rename_params(|index| format!("--dispatch-{}", index))
+            .to_string()
+    }
+
+    /// The path with the names of its params replaced and their constraints removed
+    fn rename_params(&self, name: impl Fn(usize) -> String) -> Path {
+        Path {
+            segments: self
+                .segments
+                .iter()
+                .enumerate()
+                .map(|(index, segment)| match segment {
+                    Segment::Static(_) => segment.clone(),
+                    Segment::Param { .. } => Segment::Param {
+                        name: name(index),
+                        constraints: Vec::new(),
+                    },
+                    Segment::Wildcard(_) => Segment::Wildcard(name(index)),
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Check that params in a path have a name, that names are unique and that a wildcard is only
+    /// used as the last segment
+    fn validate(&self) -> std::result::Result<(), String> {
+        let mut names: Vec<&str> = Vec::new();
+        let mut segments = self.segments.iter().peekable();
+
+        while let Some(segment) = segments.next() {
+            let name = match segment.param() {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if let (Segment::Wildcard(_), Some(_)) = (segment, segments.peek()) {
+                return Err(format!("wildcard {} is not the last segment", segment));
+            }
+            if let (Segment::Param { .. }, "") = (segment, name) {
+                return Err(format!("{} segment without a name", segment));
+            }
+            if names.contains(&name) {
+                return Err(format!("parameter {} is used more than once", name));
+            }
+            names.push(name);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = &'a Segment;
+    type IntoIter = Iter<'a, Segment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromStr for Path {
+    type Err = tide::Error;
+
+    fn from_str(pattern: &str) -> Result<Self> {
+        Path::parse(pattern)
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        formatter.write_str(&self.join(&self.segments))
+    }
+}
+
+/// Split a path fragment into segments, parentheses after a param name contain a constraint
+fn split_segments(fragment: &str) -> Result<Vec<Segment>> {
+    let mut raw_segments = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut closed = false;
+    let mut chars = fragment.chars();
+
+    while let Some(c) = chars.next() {
+        // A constraint ends its segment, only a slash can follow it
+        if closed && c != '/' {
+            return Err(RouteError::InvalidPath {
+                fragment: fragment.to_string(),
+                message: format!("unexpected text after constraint in {}", current),
+            }
+            .into());
+        }
+
+        match c {
+            '/' if depth == 0 => {
+                closed = false;
+                raw_segments.push(std::mem::take(&mut current));
+            }
+            '\\' if depth > 0 => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            '(' if current.starts_with(':') => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' if depth > 0 => {
+                depth -= 1;
+                closed = depth == 0;
+                current.push(c);
+            }
+            _ => current.push(c),
         }
     }
+
+    if depth > 0 {
+        return Err(RouteError::InvalidConstraint {
+            path: fragment.to_string(),
+            message: "unclosed parenthesis".to_string(),
+        }
+        .into());
+    }
+    raw_segments.push(current);
+
+    raw_segments
+        .into_iter()
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| parse_segment(fragment, &segment))
+        .collect()
 }
 
-impl ToString for Path {
-    fn to_string(&self) -> String {
-        self.0.clone()
+fn parse_segment(fragment: &str, segment: &str) -> Result<Segment> {
+    if let Some(wildcard) = segment.strip_prefix('*') {
+        let name = if wildcard.is_empty() {
+            "path"
+        } else {
+            wildcard
+        };
+        return Ok(Segment::Wildcard(name.to_string()));
     }
+
+    let param = match segment.strip_prefix(':') {
+        Some(param) => param,
+        None => return Ok(Segment::Static(segment.to_string())),
+    };
+
+    let (name, constraints) = match param.find('(') {
+        Some(start) => {
+            let pattern = param[start + 1..].strip_suffix(')').ok_or_else(|| {
+                RouteError::InvalidConstraint {
+                    path: fragment.to_string(),
+                    message: format!("unexpected text after constraint in {}", segment),
+                }
+            })?;
+            (&param[..start], vec![Constraint::pattern(pattern)?])
+        }
+        None => (param, Vec::new()),
+    };
+
+    Ok(Segment::Param {
+        name: name.to_string(),
+        constraints,
+    })
 }
 
 #[cfg(test)]
@@ -32,27 +475,231 @@ mod test {
 
     #[test]
     fn should_handle_slashes_between_segments() {
-        let path = Path::prefix("")
+        let path = Path::root()
             .append("/tst1/")
-            .append("tst2")
-            .append("/tst3/")
-            .append("//tst4///")
-            .append("/tst5/");
+            .and_then(|path| path.append("tst2"))
+            .and_then(|path| path.append("/tst3/"))
+            .and_then(|path| path.append("//tst4///"))
+            .and_then(|path| path.append("/tst5/"))
+            .unwrap();
 
         assert_eq!(path.to_string(), "/tst1/tst2/tst3/tst4/tst5/");
     }
 
     #[test]
     fn should_preserve_prefix_slash() {
-        let path = Path::prefix("").append("/tst1").append("tst2");
+        let path = Path::parse("/tst1")
+            .and_then(|path| path.append("tst2"))
+            .unwrap();
 
         assert_eq!(path.to_string(), "/tst1/tst2");
     }
 
     #[test]
     fn should_preserve_trailing_slash() {
-        let path = Path::prefix("").append("tst1").append("tst2/");
+        let path = Path::parse("tst1")
+            .and_then(|path| path.append("tst2/"))
+            .unwrap();
 
         assert_eq!(path.to_string(), "tst1/tst2/");
     }
+
+    #[test]
+    fn should_parse_segments() {
+        let path = Path::parse("/articles/:id/*path").unwrap();
+
+        assert_eq!(
+            path.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Segment::Static("articles".to_string()),
+                Segment::Param {
+                    name: "id".to_string(),
+                    constraints: Vec::new()
+                },
+                Segment::Wildcard("path".to_string())
+            ]
+        );
+        assert_eq!(path.params(), vec!["id", "path"]);
+        assert!(!path.is_static());
+        assert!(Path::parse("/articles/new").unwrap().is_static());
+    }
+
+    #[test]
+    fn should_split_inline_constraints() {
+        let path = Path::root()
+            .append(r"articles/:id(\d+)/:slug((draft|final)-[a-z\)]+)")
+            .unwrap();
+
+        assert_eq!(path.to_string(), "/articles/:id/:slug");
+        assert_eq!(
+            path.iter().skip(1).cloned().collect::<Vec<_>>(),
+            vec![
+                Segment::Param {
+                    name: "id".to_string(),
+                    constraints: vec![Constraint::pattern(r"\d+").unwrap()]
+                },
+                Segment::Param {
+                    name: "slug".to_string(),
+                    constraints: vec![Constraint::pattern(r"(draft|final)-[a-z\)]+").unwrap()]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn should_fail_on_unclosed_constraint() {
+        assert!(Path::root().append(r":id(\d+").is_err());
+        assert!(Path::root().append(r":id(\d+)x").is_err());
+    }
+
+    #[test]
+    fn should_match_request_paths() {
+        let path = Path::root()
+            .append("articles/:id")
+            .and_then(|path| path.constrain(Constraint::Numeric))
+            .and_then(|path| path.append("comments"))
+            .unwrap();
+
+        assert!(path.matches("/articles/12/comments"));
+        assert!(!path.matches("/articles/twelve/comments"));
+        assert!(!path.matches("/articles/12"));
+        assert!(!path.matches("/articles/12/comments/3"));
+        assert!(!path.matches("/posts/12/comments"));
+        assert!(!path.matches("/articles/12/comments/"));
+    }
+
+    #[test]
+    fn should_match_rest_of_path_with_wildcard() {
+        let path = Path::parse("/files/*path").unwrap();
+
+        assert!(path.matches("/files/images/logo.png"));
+        assert!(!path.matches("/files"));
+        assert!(path.matches("/files/images/"));
+    }
+
+    #[test]
+    fn should_fail_constraining_path_without_params() {
+        assert!(Path::root()
+            .append("articles")
+            .and_then(|path| path.constrain(Constraint::Numeric))
+            .is_err());
+    }
+
+    #[test]
+    fn should_compare_specificity() {
+        let path = |pattern| Path::parse(pattern).unwrap();
+
+        assert_eq!(
+            path("/articles/new").cmp_specificity(&path(r"/articles/:id(\d+)")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            path(r"/articles/:id(\d+)").cmp_specificity(&path("/articles/:slug")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            path("/articles/*path").cmp_specificity(&path("/articles/:slug")),
+            Ordering::Less
+        );
+        assert_eq!(
+            path("/articles/:id").cmp_specificity(&path("/articles/:slug")),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn should_describe_constraints() {
+        let path = Path::parse(r"/articles/:id(\d+)/*rest")
+            .unwrap()
+            .constrain(Constraint::OneOf(vec!["1".to_string(), "2.0".to_string()]))
+            .unwrap();
+
+        assert_eq!(path.to_string(), "/articles/:id/*rest");
+        assert_eq!(path.describe(), r"/articles/:id(\d+)(1|2\.0)/*rest");
+    }
+
+    #[test]
+    fn should_detect_overlapping_paths() {
+        let path = |pattern| Path::parse(pattern).unwrap();
+
+        assert!(path("/articles/new").overlaps(&path("/articles/:id")));
+        assert!(path("/files/*path").overlaps(&path("/files/images/logo.png")));
+        assert!(!path("/files/*path").overlaps(&path("/files")));
+        assert!(!path(r"/articles/:id(\d+)").overlaps(&path("/articles/new")));
+        assert!(!path("/articles/:id").overlaps(&path("/articles/:id/comments")));
+        assert!(!path("/articles/:id").overlaps(&path("/articles/new/")));
+        assert!(path("/articles/*path").overlaps(&path("/articles/new/")));
+    }
+
+    #[test]
+    fn should_ignore_param_names_in_shape() {
+        let path = Path::parse("/articles/:id/*path").unwrap();
+
+        assert_eq!(path.shape(), "/articles/:/*");
+    }
+
+    fn path_error(fragment: &str) -> String {
+        let error = Path::root()
+            .append("articles/:id")
+            .and_then(|path| path.append(fragment))
+            .unwrap_err();
+
+        match error.downcast_ref::<RouteError>() {
+            Some(RouteError::InvalidPath { fragment, message }) => {
+                format!("{}: {}", fragment, message)
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_fail_on_param_without_name() {
+        assert_eq!(
+            path_error("comments/:"),
+            "comments/:: : segment without a name"
+        );
+    }
+
+    #[test]
+    fn should_name_wildcard_without_name() {
+        let path = Path::root().append("*").unwrap();
+
+        assert_eq!(path.to_string(), "/*path");
+        assert_eq!(path.params(), vec!["path"]);
+        assert!(path.matches("/articles/12"));
+    }
+
+    #[test]
+    fn should_fail_on_text_after_constraint() {
+        assert_eq!(
+            path_error(r":slug(\d+)x(y)"),
+            r":slug(\d+)x(y): unexpected text after constraint in :slug(\d+)"
+        );
+        assert!(Path::root().append(r":id(\d+)/edit").is_ok());
+    }
+
+    #[test]
+    fn should_fail_on_duplicate_param() {
+        assert_eq!(
+            path_error("comments/:id"),
+            "comments/:id: parameter id is used more than once"
+        );
+    }
+
+    #[test]
+    fn should_fail_on_wildcard_before_last_segment() {
+        assert_eq!(
+            path_error("*path/edit"),
+            "*path/edit: wildcard *path is not the last segment"
+        );
+        assert_eq!(
+            path_error("*path/*rest"),
+            "*path/*rest: wildcard *path is not the last segment"
+        );
+    }
+
+    #[test]
+    fn should_allow_trailing_slash_after_wildcard() {
+        assert!(Path::root().append("files/*path/").is_ok());
+    }
 }
@@ -1,39 +1,395 @@
 //! The reverse router returns routes by their name.
 
-use crate::HashMap;
+use crate::constraint::Constraint;
+pub use crate::error::ResolveError;
+use crate::error::{DuplicateName, RouteError};
+use crate::path::{Path, Segment};
+use crate::{HashMap, Result};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tide::http::proxies::Forwarded;
+use tide::http::Method;
+use tide::{utils::async_trait, Body, Endpoint, Middleware, Next, Request};
 
 /// Stores a list of routes by name
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ReverseRouter {
-    routes: HashMap<String, String>,
+    routes: HashMap<String, NamedRoute>,
+    base_url: Option<BaseUrl>,
+}
+
+#[derive(Debug)]
+struct NamedRoute {
+    template: String,
+    path: Path,
+    method: Option<Method>,
 }
 
 impl ReverseRouter {
-    /// Insert a named route
-    pub fn insert(&mut self, name: &str, route: &str) {
-        self.routes.insert(name.to_string(), route.to_string());
+    /// Insert a named route, fails when a route with the same name was already inserted. The
+    /// route can contain inline constraints like `:id(\d+)`
+    pub fn insert(&mut self, name: &str, route: &str) -> Result<()> {
+        self.insert_route(name, Path::parse(route)?, None)
     }
 
-    /// Resolve a named route
-    pub fn resolve(&self, name: &str, _params: Params) -> String {
-        let route = self.routes[name].clone();
+    /// Insert a named route for a single HTTP method, fails when a route with the same name was
+    /// already inserted
+    pub fn insert_method(&mut self, name: &str, method: Method, route: &str) -> Result<()> {
+        self.insert_route(name, Path::parse(route)?, Some(method))
+    }
+
+    pub(crate) fn insert_route(
+        &mut self,
+        name: &str,
+        path: Path,
+        method: Option<Method>,
+    ) -> Result<()> {
+        if let Some(existing) = self.routes.get(name) {
+            return Err(RouteError::DuplicateNames(vec![DuplicateName {
+                name: name.to_string(),
+                paths: vec![existing.path.describe(), path.describe()],
+            }])
+            .into());
+        }
 
-        // todo: replace params with values
+        let route = NamedRoute {
+            template: path.to_string(),
+            path,
+            method,
+        };
+        self.routes.insert(name.to_string(), route);
+        Ok(())
+    }
 
-        route
+    /// The HTTP method of a route that was named for a single method, returns `None` for routes
+    /// that are named for all methods and for unknown routes
+    pub fn method(&self, name: &str) -> Option<Method> {
+        self.routes.get(name).and_then(|route| route.method)
+    }
+
+    /// Resolve a named route, replaces `:param` and `*wildcard` segments with values from params
+    /// and checks values against the constraints on the parameters
+    pub fn resolve(&self, name: &str, params: Params) -> std::result::Result<String, ResolveError> {
+        let route = &self
+            .routes
+            .get(name)
+            .ok_or_else(|| ResolveError::UnknownRoute(name.to_string()))?
+            .path;
+
+        let mut used: Vec<&str> = Vec::new();
+        let segments = route
+            .iter()
+            .map(|segment| {
+                let param = match segment.param() {
+                    Some(param) => param,
+                    None => return Ok(segment.to_string()),
+                };
+                used.push(param);
+
+                let value = params
+                    .get(param)
+                    .ok_or_else(|| ResolveError::MissingParam {
+                        route: name.to_string(),
+                        param: param.to_string(),
+                    })?;
+
+                if value.is_empty() {
+                    return Err(ResolveError::EmptyParam {
+                        route: name.to_string(),
+                        param: param.to_string(),
+                    });
+                }
+
+                if let Segment::Param { constraints, .. } = segment {
+                    if !constraints
+                        .iter()
+                        .all(|constraint| value.satisfies(constraint))
+                    {
+                        return Err(ResolveError::InvalidParam {
+                            route: name.to_string(),
+                            param: param.to_string(),
+                        });
+                    }
+                }
+
+                Ok(match (value, segment) {
+                    (Value::Raw(value), _) => value.clone(),
+                    (Value::Encode(value), Segment::Wildcard(_)) => value
+                        .split('/')
+                        .map(encode_segment)
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    (Value::Encode(value), _) => encode_segment(value),
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let unused: Vec<(&str, &Value)> = params
+            .iter()
+            .filter(|(param, _)| !used.contains(param))
+            .collect();
+
+        if !unused.is_empty() && !params.query {
+            return Err(ResolveError::UnusedParams {
+                route: name.to_string(),
+                params: unused.iter().map(|(param, _)| param.to_string()).collect(),
+            });
+        }
+
+        let mut url = route.join(&segments);
+
+        if !unused.is_empty() {
+            let query: Vec<String> = unused
+                .iter()
+                .map(|(param, value)| format!("{}={}", encode(param), value.encoded()))
+                .collect();
+            url.push('?');
+            url.push_str(&query.join("&"));
+        }
+
+        if let Some(fragment) = &params.fragment {
+            url.push('#');
+            url.push_str(&encode(fragment));
+        }
+
+        Ok(url)
+    }
+
+    /// Resolve a typed route declared with the `typed_routes!` macro
+    pub fn resolve_route<R: TypedRoute>(
+        &self,
+        route: &R,
+    ) -> std::result::Result<String, ResolveError> {
+        self.resolve(R::NAME, route.params())
+    }
+
+    /// Resolve a named route to an absolute url using the configured base url
+    pub fn resolve_absolute(
+        &self,
+        name: &str,
+        params: Params,
+    ) -> std::result::Result<String, ResolveError> {
+        let base_url = self.base_url.as_ref().ok_or(ResolveError::NoBaseUrl)?;
+        self.resolve_with_base(base_url, name, params)
+    }
+
+    /// Resolve a named route to an absolute url using the specified base url
+    pub fn resolve_with_base(
+        &self,
+        base_url: &BaseUrl,
+        name: &str,
+        params: Params,
+    ) -> std::result::Result<String, ResolveError> {
+        Ok(base_url.join(&self.resolve(name, params)?))
+    }
+
+    /// Set the base url for resolving absolute urls
+    pub fn set_base_url(&mut self, base_url: BaseUrl) {
+        self.base_url = Some(base_url);
+    }
+
+    /// The base url for resolving absolute urls, if configured
+    pub fn base_url(&self) -> Option<&BaseUrl> {
+        self.base_url.as_ref()
+    }
+
+    /// The names and path templates of all routes, sorted by name
+    pub fn routes(&self) -> Vec<(&str, &str)> {
+        let mut routes: Vec<_> = self
+            .routes
+            .iter()
+            .map(|(name, route)| (name.as_str(), route.template.as_str()))
+            .collect();
+        routes.sort();
+        routes
+    }
+
+    /// The names and parsed paths of all routes, sorted by name
+    pub fn paths(&self) -> Vec<(&str, &Path)> {
+        let mut paths: Vec<_> = self
+            .routes
+            .iter()
+            .map(|(name, route)| (name.as_str(), &route.path))
+            .collect();
+        paths.sort_by_key(|(name, _)| *name);
+        paths
     }
 
     /// Construct a named routes list
     pub fn new() -> Self {
-        ReverseRouter {
-            routes: HashMap::new(),
+        Self::default()
+    }
+}
+
+/// Serializes as a map from route name to the path template of the route, the names of the
+/// parameters in the path and the HTTP method for routes that are named for a single method. Routes
+/// are sorted by name
+impl Serialize for ReverseRouter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.routes
+            .iter()
+            .map(|(name, route)| {
+                let template = RouteTemplate {
+                    path: route.path.to_string(),
+                    params: route.path.params(),
+                    method: route.method.map(|method| method.to_string()),
+                };
+                (name, template)
+            })
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+}
+
+#[derive(Serialize)]
+struct RouteTemplate<'a> {
+    path: String,
+    params: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+}
+
+/// The scheme, host and optional mount prefix that are put in front of paths to make absolute urls
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaseUrl {
+    scheme: String,
+    host: String,
+    prefix: String,
+}
+
+impl BaseUrl {
+    /// Construct a base url from a scheme like `https` and a host, the host can include a port
+    pub fn new(scheme: &str, host: &str) -> Self {
+        Self {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            prefix: String::new(),
         }
     }
+
+    /// Add a prefix for applications that are not mounted at the root of the host
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        self.prefix = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", prefix)
+        };
+        self
+    }
+
+    /// Derive the base url from a request. The scheme is taken from the `proto` of the `Forwarded`
+    /// header or the `X-Forwarded-Proto` header, the host from the `host` of the `Forwarded` header
+    /// or the `X-Forwarded-Host` or `Host` headers and the prefix from the `X-Forwarded-Prefix`
+    /// header. Without these headers the scheme, host and port of the request url are used. Only
+    /// use this behind a proxy that sets these headers.
+    pub fn from_request<State>(request: &Request<State>) -> Option<Self> {
+        let url = request.url();
+        let forwarded = Forwarded::from_forwarded_header(request).ok().flatten();
+
+        let first = |name: &str| {
+            request
+                .header(name)
+                .and_then(|value| value.as_str().split(',').next())
+                .map(str::trim)
+        };
+
+        let scheme = forwarded
+            .as_ref()
+            .and_then(Forwarded::proto)
+            .or_else(|| first("X-Forwarded-Proto"))
+            .unwrap_or_else(|| url.scheme());
+
+        let host = match forwarded
+            .as_ref()
+            .and_then(Forwarded::host)
+            .or_else(|| first("X-Forwarded-Host"))
+            .or_else(|| request.header("Host").map(|host| host.as_str()))
+        {
+            Some(host) => host.to_string(),
+            None => match (url.host_str()?, url.port()) {
+                (host, Some(port)) => format!("{}:{}", host, port),
+                (host, None) => host.to_string(),
+            },
+        };
+
+        let base_url = BaseUrl::new(scheme, &host);
+
+        Some(match request.header("X-Forwarded-Prefix") {
+            Some(prefix) => base_url.with_prefix(prefix.as_str()),
+            None => base_url,
+        })
+    }
+
+    fn join(&self, path: &str) -> String {
+        format!("{}://{}{}{}", self.scheme, self.host, self.prefix, path)
+    }
 }
 
-/// Parameters for insertion in paths
+/// Characters that are encoded in path segments, query strings and fragments, everything except
+/// the unreserved characters from RFC 3986
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, COMPONENT).to_string()
+}
+
+/// Encode a path segment, `.` and `..` are encoded as well because they would be removed when the
+/// url is normalized
+fn encode_segment(value: &str) -> String {
+    match value {
+        "." => "%2E".to_string(),
+        ".." => "%2E%2E".to_string(),
+        _ => encode(value),
+    }
+}
+
+/// A parameter value, either to be percent-encoded or already encoded
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Encode(String),
+    Raw(String),
+}
+
+impl Value {
+    fn is_empty(&self) -> bool {
+        match self {
+            Value::Encode(value) | Value::Raw(value) => value.is_empty(),
+        }
+    }
+
+    fn encoded(&self) -> String {
+        match self {
+            Value::Encode(value) => encode(value),
+            Value::Raw(value) => value.clone(),
+        }
+    }
+
+    /// Check the value against a constraint, raw values are decoded before checking
+    fn satisfies(&self, constraint: &Constraint) -> bool {
+        match self {
+            Value::Encode(value) => constraint.matches(value),
+            Value::Raw(value) => percent_decode_str(value)
+                .decode_utf8()
+                .map_or(false, |value| constraint.matches(&value)),
+        }
+    }
+}
+
+/// Parameters for insertion in paths. Values are percent-encoded when they are inserted into a url,
+/// `:param` values are encoded as a single path segment, slashes in `*wildcard` values are kept.
 #[derive(Debug, Clone, PartialEq, Default)]
-pub struct Params(HashMap<String, String>);
+pub struct Params {
+    values: BTreeMap<String, Value>,
+    query: bool,
+    fragment: Option<String>,
+}
 
 impl Params {
     /// Create new params
@@ -43,19 +399,249 @@ impl Params {
 
     /// Insert a parameter
     pub fn insert<P: ToString, V: ToString>(&mut self, param: P, value: V) {
-        self.0.insert(param.to_string(), value.to_string());
+        self.values
+            .insert(param.to_string(), Value::Encode(value.to_string()));
+    }
+
+    /// Insert a parameter that is already percent-encoded, the value is inserted into the url as is
+    pub fn insert_raw<P: ToString, V: ToString>(&mut self, param: P, value: V) {
+        self.values
+            .insert(param.to_string(), Value::Raw(value.to_string()));
+    }
+
+    /// Add parameters that do not appear in the path of the route to the query string instead of
+    /// failing to resolve the route. Query parameters are sorted by name.
+    pub fn with_query(mut self) -> Self {
+        self.query = true;
+        self
+    }
+
+    /// Add a fragment to the resolved url
+    pub fn with_fragment<F: ToString>(mut self, fragment: F) -> Self {
+        self.fragment = Some(fragment.to_string());
+        self
+    }
+
+    fn get(&self, param: &str) -> Option<&Value> {
+        self.values.get(param)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.values
+            .iter()
+            .map(|(param, value)| (param.as_str(), value))
+    }
+}
+
+/// Middleware that makes the reverse router available to endpoints through request extensions
+#[derive(Debug)]
+pub(crate) struct ReverseRouterMiddleware(Arc<ReverseRouter>);
+
+impl ReverseRouterMiddleware {
+    pub(crate) fn new(router: Arc<ReverseRouter>) -> Self {
+        Self(router)
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for ReverseRouterMiddleware {
+    async fn handle(&self, mut request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        request.set_ext(self.0.clone());
+        Ok(next.run(request).await)
+    }
+}
+
+/// Endpoint serving the serialized reverse router as json
+#[derive(Debug)]
+pub(crate) struct RouteTableEndpoint(String);
+
+impl RouteTableEndpoint {
+    pub(crate) fn new(router: &ReverseRouter) -> Result<Self> {
+        Ok(Self(serde_json::to_string(router)?))
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for RouteTableEndpoint {
+    async fn call(&self, _req: Request<State>) -> tide::Result {
+        let mut body = Body::from_string(self.0.clone());
+        body.set_mime(tide::http::mime::JSON);
+        Ok(body.into())
+    }
+}
+
+/// Extension methods for resolving named routes from a request
+pub trait UrlFor {
+    /// Resolve a named route using the reverse router from the request
+    fn url_for(&self, name: &str, params: Params) -> std::result::Result<String, ResolveError>;
+
+    /// Resolve a typed route declared with the `typed_routes!` macro using the reverse router from
+    /// the request
+    fn url_for_route<R: TypedRoute>(&self, route: &R) -> std::result::Result<String, ResolveError>;
+
+    /// Resolve a named route to an absolute url using the base url configured on the reverse
+    /// router
+    fn absolute_url_for(
+        &self,
+        name: &str,
+        params: Params,
+    ) -> std::result::Result<String, ResolveError>;
+
+    /// Resolve a named route to an absolute url using a base url derived from the headers of the
+    /// request, see `BaseUrl::from_request`
+    fn request_url_for(
+        &self,
+        name: &str,
+        params: Params,
+    ) -> std::result::Result<String, ResolveError>;
+}
+
+impl<State> UrlFor for Request<State> {
+    fn url_for(&self, name: &str, params: Params) -> std::result::Result<String, ResolveError> {
+        reverse_router(self)?.resolve(name, params)
     }
+
+    fn url_for_route<R: TypedRoute>(&self, route: &R) -> std::result::Result<String, ResolveError> {
+        reverse_router(self)?.resolve_route(route)
+    }
+
+    fn absolute_url_for(
+        &self,
+        name: &str,
+        params: Params,
+    ) -> std::result::Result<String, ResolveError> {
+        reverse_router(self)?.resolve_absolute(name, params)
+    }
+
+    fn request_url_for(
+        &self,
+        name: &str,
+        params: Params,
+    ) -> std::result::Result<String, ResolveError> {
+        let base_url = BaseUrl::from_request(self).ok_or(ResolveError::NoBaseUrl)?;
+        reverse_router(self)?.resolve_with_base(&base_url, name, params)
+    }
+}
+
+fn reverse_router<State>(
+    request: &Request<State>,
+) -> std::result::Result<&ReverseRouter, ResolveError> {
+    request
+        .ext::<Arc<ReverseRouter>>()
+        .map(Arc::as_ref)
+        .ok_or(ResolveError::NoReverseRouter)
+}
+
+/// A route name with typed parameters, declare typed routes using the `typed_routes!` macro
+pub trait TypedRoute {
+    /// The name of the route
+    const NAME: &'static str;
+
+    /// The names of the parameters in the path of the route
+    const PARAMS: &'static [&'static str];
+
+    /// The parameters for resolving the route
+    fn params(&self) -> Params;
+}
+
+/// Declare typed routes. Every route becomes a struct with a field for every parameter in its
+/// path, name segments with `RouteBuilder::typed_name` and resolve them with
+/// `ReverseRouter::resolve_route` or `UrlFor::url_for_route`. Using a route that is not declared
+/// or passing the wrong parameters will fail to compile;
+/// ```rust
+/// # use tide::{Request, Result};
+/// # async fn endpoint(_: Request<()>) -> Result {
+/// #     todo!()
+/// # }
+/// use tide_fluent_routes::{prelude::*, typed_routes};
+///
+/// typed_routes! {
+///     /// The list of articles
+///     pub struct Articles = "articles";
+///
+///     /// A single article
+///     pub struct Article = "article" { id: u64 }
+/// }
+///
+/// let router = root::<()>()
+///     .at("articles", |route| route
+///         .typed_name::<Articles>()
+///         .at(":id", |route| route.typed_name::<Article>().get(endpoint))
+///     )
+///     .and_then(|routes| routes.reverse_router())
+///     .expect("Error setting up routes");
+///
+/// assert_eq!(router.resolve_route(&Article { id: 5 }), Ok("/articles/5".to_string()));
+/// ```
+/// Naming a segment after a typed route with fields that differ from the parameters in the path
+/// of the segment fails when building the route tree. Passing parameters the route does not have
+/// does not compile;
+/// ```rust,compile_fail
+/// # use tide_fluent_routes::{prelude::*, typed_routes};
+/// typed_routes! {
+///     pub struct Article = "article" { id: u64 }
+/// }
+///
+/// # let router = ReverseRouter::new();
+/// router.resolve_route(&Article { slug: "first" });
+/// ```
+#[macro_export]
+macro_rules! typed_routes {
+    () => {};
+
+    ($(#[$meta:meta])* $vis:vis struct $route:ident = $name:literal; $($rest:tt)*) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        $vis struct $route;
+
+        impl $crate::reverse_router::TypedRoute for $route {
+            const NAME: &'static str = $name;
+            const PARAMS: &'static [&'static str] = &[];
+
+            fn params(&self) -> $crate::reverse_router::Params {
+                $crate::reverse_router::Params::new()
+            }
+        }
+
+        $crate::typed_routes! { $($rest)* }
+    };
+
+    (
+        $(#[$meta:meta])* $vis:vis struct $route:ident = $name:literal {
+            $($(#[$field_meta:meta])* $field:ident: $type:ty),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        $vis struct $route {
+            $($(#[$field_meta])* pub $field: $type),*
+        }
+
+        impl $crate::reverse_router::TypedRoute for $route {
+            const NAME: &'static str = $name;
+            const PARAMS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn params(&self) -> $crate::reverse_router::Params {
+                let mut params = $crate::reverse_router::Params::new();
+                $(params.insert(stringify!($field), &self.$field);)*
+                params
+            }
+        }
+
+        $crate::typed_routes! { $($rest)* }
+    };
 }
 
 /// Construct parameters for the reverse router
 #[macro_export]
 macro_rules! params {
     () => {{
-        Params::new()
+        $crate::reverse_router::Params::new()
     }};
 
     ($( $param:expr => $value:expr ),+ ) => {{
-        let mut pm: Params = Params::new();
+        let mut pm = $crate::reverse_router::Params::new();
         $(pm.insert($param.to_string(), $value);)*
         pm
     }};
@@ -64,6 +650,10 @@ macro_rules! params {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::call;
+    use percent_encoding::percent_decode_str;
+    use tide::http::{self, Method, Url};
+    use tide::Request;
 
     #[test]
     fn should_construct_empty_params() {
@@ -91,4 +681,383 @@ mod test {
 
         assert_eq!(params, expected);
     }
+
+    #[test]
+    fn should_fail_inserting_duplicate_name() {
+        let mut router = ReverseRouter::new();
+
+        router.insert("name", "/first").unwrap();
+        assert!(router.insert("name", "/second").is_err());
+    }
+
+    fn router() -> ReverseRouter {
+        let mut router = ReverseRouter::new();
+        router.insert("root", "/").unwrap();
+        router.insert("article", "/articles/:id").unwrap();
+        router
+            .insert("comment", "/articles/:article/comments/:id")
+            .unwrap();
+        router.insert("file", "/files/*path").unwrap();
+        router
+    }
+
+    #[test]
+    fn should_list_parsed_paths() {
+        let router = router();
+        let paths: Vec<_> = router
+            .paths()
+            .into_iter()
+            .map(|(name, path)| (name, path.params()))
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                ("article", vec!["id"]),
+                ("comment", vec!["article", "id"]),
+                ("file", vec!["path"]),
+                ("root", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_resolve_static_route() {
+        assert_eq!(router().resolve("root", params! {}), Ok("/".to_string()));
+    }
+
+    #[test]
+    fn should_substitute_params() {
+        assert_eq!(
+            router().resolve("comment", params! {"article" => 5, "id" => 12}),
+            Ok("/articles/5/comments/12".to_string())
+        );
+    }
+
+    #[test]
+    fn should_substitute_wildcard() {
+        assert_eq!(
+            router().resolve("file", params! {"path" => "images/logo.png"}),
+            Ok("/files/images/logo.png".to_string())
+        );
+    }
+
+    #[test]
+    fn should_resolve_unnamed_wildcard_by_its_generated_name() {
+        let mut router = ReverseRouter::new();
+        router.insert("files", "/files/*").unwrap();
+
+        assert_eq!(
+            router.resolve("files", params! {"path" => "images/logo.png"}),
+            Ok("/files/images/logo.png".to_string())
+        );
+        assert_eq!(
+            serde_json::to_value(router).unwrap(),
+            serde_json::json!({"files": {"path": "/files/*path", "params": ["path"]}})
+        );
+    }
+
+    #[test]
+    fn should_fail_resolving_unknown_route() {
+        assert_eq!(
+            router().resolve("unknown", params! {}),
+            Err(ResolveError::UnknownRoute("unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_fail_resolving_with_missing_param() {
+        assert_eq!(
+            router().resolve("comment", params! {"article" => 5}),
+            Err(ResolveError::MissingParam {
+                route: "comment".to_string(),
+                param: "id".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn should_fail_resolving_with_unused_params() {
+        assert_eq!(
+            router().resolve("article", params! {"id" => 5, "page" => 2, "extra" => 1}),
+            Err(ResolveError::UnusedParams {
+                route: "article".to_string(),
+                params: vec!["extra".to_string(), "page".to_string()]
+            })
+        );
+    }
+
+    #[test]
+    fn should_add_unused_params_to_query() {
+        assert_eq!(
+            router().resolve(
+                "article",
+                params! {"sort" => "desc", "id" => 5, "page" => 2}.with_query()
+            ),
+            Ok("/articles/5?page=2&sort=desc".to_string())
+        );
+    }
+
+    #[test]
+    fn should_encode_query() {
+        assert_eq!(
+            router().resolve(
+                "root",
+                params! {"q" => "fish & chips", "tag=" => "a/b?"}.with_query()
+            ),
+            Ok("/?q=fish%20%26%20chips&tag%3D=a%2Fb%3F".to_string())
+        );
+    }
+
+    #[test]
+    fn should_not_add_empty_query() {
+        assert_eq!(
+            router().resolve("article", params! {"id" => 5}.with_query()),
+            Ok("/articles/5".to_string())
+        );
+    }
+
+    #[test]
+    fn should_add_fragment() {
+        assert_eq!(
+            router().resolve(
+                "article",
+                params! {"id" => 5, "page" => 2}
+                    .with_query()
+                    .with_fragment("comment 3")
+            ),
+            Ok("/articles/5?page=2#comment%203".to_string())
+        );
+    }
+
+    #[test]
+    fn should_encode_param_as_single_segment() {
+        assert_eq!(
+            router().resolve("article", params! {"id" => "a b/c?"}),
+            Ok("/articles/a%20b%2Fc%3F".to_string())
+        );
+    }
+
+    #[test]
+    fn should_keep_slashes_in_wildcard() {
+        assert_eq!(
+            router().resolve("file", params! {"path" => "my images/a#1.png"}),
+            Ok("/files/my%20images/a%231.png".to_string())
+        );
+    }
+
+    #[test]
+    fn should_encode_dot_segments() {
+        assert_eq!(
+            router().resolve("article", params! {"id" => ".."}),
+            Ok("/articles/%2E%2E".to_string())
+        );
+        assert_eq!(
+            router().resolve("file", params! {"path" => "./../a.png"}),
+            Ok("/files/%2E/%2E%2E/a.png".to_string())
+        );
+    }
+
+    #[test]
+    fn should_fail_resolving_with_empty_param() {
+        assert_eq!(
+            router().resolve("article", params! {"id" => ""}),
+            Err(ResolveError::EmptyParam {
+                route: "article".to_string(),
+                param: "id".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn should_insert_raw_params_unencoded() {
+        let mut params = Params::new();
+        params.insert_raw("id", "a%20b");
+        params.insert_raw("page", "1+2");
+
+        assert_eq!(
+            router().resolve("article", params.with_query()),
+            Ok("/articles/a%20b?page=1+2".to_string())
+        );
+    }
+
+    async fn echo(server: &tide::Server<()>, request: http::Request) -> String {
+        let mut response: http::Response = server.respond(request).await.unwrap();
+        response.body_string().await.unwrap()
+    }
+
+    async fn echo_param(server: &tide::Server<()>, url: &str) -> String {
+        call(server, Method::Get, url).await.1
+    }
+
+    #[async_std::test]
+    async fn should_round_trip_params_through_tide() {
+        let mut server = tide::Server::new();
+        server
+            .at("/articles/:id")
+            .get(|req: Request<()>| async move {
+                Ok(percent_decode_str(req.param("id")?)
+                    .decode_utf8()?
+                    .to_string())
+            });
+        server
+            .at("/files/*path")
+            .get(|req: Request<()>| async move {
+                Ok(percent_decode_str(req.param("path")?)
+                    .decode_utf8()?
+                    .to_string())
+            });
+
+        for value in &["plain", "a b/c?", "100% #1", "ünï/cødé", "x&y=z"] {
+            let url = router()
+                .resolve("article", params! {"id" => value})
+                .unwrap();
+            assert_eq!(&echo_param(&server, &url).await, value);
+        }
+
+        for value in &["plain", "dir/a b.txt", "a/b/c?d#e", "ünï/cødé"] {
+            let url = router().resolve("file", params! {"path" => value}).unwrap();
+            assert_eq!(&echo_param(&server, &url).await, value);
+        }
+    }
+
+    #[test]
+    fn should_resolve_absolute_url() {
+        let mut router = router();
+        router.set_base_url(BaseUrl::new("https", "example.com:8443").with_prefix("/app/"));
+
+        assert_eq!(
+            router.resolve_absolute("article", params! {"id" => 5}),
+            Ok("https://example.com:8443/app/articles/5".to_string())
+        );
+    }
+
+    #[test]
+    fn should_fail_resolving_absolute_url_without_base_url() {
+        assert_eq!(
+            router().resolve_absolute("article", params! {"id" => 5}),
+            Err(ResolveError::NoBaseUrl)
+        );
+    }
+
+    #[async_std::test]
+    async fn should_derive_base_url_from_request() {
+        let mut server = tide::Server::new();
+        server.at("/").get(|req: Request<()>| async move {
+            Ok(format!("{:?}", BaseUrl::from_request(&req)))
+        });
+
+        let mut request =
+            http::Request::new(Method::Get, Url::parse("http://internal:8080/").unwrap());
+        request.insert_header("Host", "internal:8080");
+        assert_eq!(
+            echo(&server, request).await,
+            format!("{:?}", Some(BaseUrl::new("http", "internal:8080")))
+        );
+
+        let mut request =
+            http::Request::new(Method::Get, Url::parse("http://internal:8080/").unwrap());
+        request.insert_header("Host", "internal:8080");
+        request.insert_header("X-Forwarded-Host", "example.com");
+        request.insert_header("X-Forwarded-Proto", "https");
+        request.insert_header("X-Forwarded-Prefix", "/app");
+        assert_eq!(
+            echo(&server, request).await,
+            format!(
+                "{:?}",
+                Some(BaseUrl::new("https", "example.com").with_prefix("app"))
+            )
+        );
+
+        let mut request =
+            http::Request::new(Method::Get, Url::parse("http://internal:8080/").unwrap());
+        request.insert_header("Host", "internal:8080");
+        request.insert_header("Forwarded", "for=192.0.2.43;host=example.com;proto=https");
+        request.insert_header("X-Forwarded-Proto", "http");
+        assert_eq!(
+            echo(&server, request).await,
+            format!("{:?}", Some(BaseUrl::new("https", "example.com")))
+        );
+
+        let request = http::Request::new(Method::Get, Url::parse("http://internal:8080/").unwrap());
+        assert_eq!(
+            echo(&server, request).await,
+            format!("{:?}", Some(BaseUrl::new("http", "internal:8080")))
+        );
+    }
+
+    #[test]
+    fn should_serialize_route_templates() {
+        assert_eq!(
+            serde_json::to_value(router()).unwrap(),
+            serde_json::json!({
+                "article": {"path": "/articles/:id", "params": ["id"]},
+                "comment": {"path": "/articles/:article/comments/:id", "params": ["article", "id"]},
+                "file": {"path": "/files/*path", "params": ["path"]},
+                "root": {"path": "/", "params": []}
+            })
+        );
+    }
+
+    typed_routes! {
+        struct Root = "root";
+        struct Comment = "comment" { article: u64, id: String }
+        struct File = "file" {
+            /// The path of the file
+            path: String,
+        }
+    }
+
+    #[test]
+    fn should_resolve_typed_routes() {
+        let router = router();
+
+        assert_eq!(router.resolve_route(&Root), Ok("/".to_string()));
+        assert_eq!(
+            router.resolve_route(&Comment {
+                article: 5,
+                id: "first".to_string()
+            }),
+            Ok("/articles/5/comments/first".to_string())
+        );
+        assert_eq!(
+            router.resolve_route(&File {
+                path: "a b/c".to_string()
+            }),
+            Ok("/files/a%20b/c".to_string())
+        );
+    }
+
+    #[test]
+    fn should_record_method_of_named_route() {
+        let mut router = router();
+        router
+            .insert_method("article.update", Method::Put, "/articles/:id")
+            .unwrap();
+
+        assert_eq!(router.method("article.update"), Some(Method::Put));
+        assert_eq!(router.method("article"), None);
+        assert_eq!(
+            serde_json::to_value(&router).unwrap()["article.update"],
+            serde_json::json!({"path": "/articles/:id", "params": ["id"], "method": "PUT"})
+        );
+    }
+
+    #[test]
+    fn should_fail_resolving_with_invalid_param() {
+        let mut router = router();
+        router.insert("page", r"/pages/:page(\d+)").unwrap();
+
+        assert_eq!(
+            router.resolve("page", params! {"page" => 3}),
+            Ok("/pages/3".to_string())
+        );
+        assert_eq!(
+            router.resolve("page", params! {"page" => "three"}),
+            Err(ResolveError::InvalidParam {
+                route: "page".to_string(),
+                param: "page".to_string()
+            })
+        );
+    }
 }
@@ -1,6 +1,15 @@
 //! The RouteBuilder trait defines the internal dsl to build route trees as implemented by all
 //! RouteSegments
 
+use crate::constraint::Constraint;
+use crate::cors::{CorsMiddleware, CorsPolicy};
+use crate::openapi::{Info, Operation};
+use crate::param::ParseParam;
+use crate::reverse_router::TypedRoute;
+use crate::trailing_slash::TrailingSlash;
+use std::any::Any;
+use std::panic::Location;
+use std::str::FromStr;
 use tide::http::Method;
 use tide::{Endpoint, Middleware};
 
@@ -10,65 +19,203 @@ pub trait RouteBuilder<State: Clone + Send + Sync + 'static>: Sized {
     /// Add a path segment with a set of sub-routes
     fn at<R: FnOnce(Self) -> Self>(self, path: &str, routes: R) -> Self;
 
+    /// Add a path segment with a set of sub-routes, the last parameter in the path only matches
+    /// values that satisfy the constraint
+    fn at_matching<R: FnOnce(Self) -> Self>(
+        self,
+        path: &str,
+        constraint: Constraint,
+        routes: R,
+    ) -> Self;
+
     /// Add middleware with a set of sub-routes
     fn with<M: Middleware<State>, R: FnOnce(Self) -> Self>(self, middleware: M, routes: R) -> Self;
 
     /// Add an endpoint for an http method
     fn method(self, method: Method, endpoint: impl Endpoint<State>) -> Self;
 
+    /// Add a named endpoint for an http method, the name is used for this method only
+    fn method_named(self, method: Method, name: &str, endpoint: impl Endpoint<State>) -> Self;
+
     /// Add a catchall endpoint
     fn all(self, endpoint: impl Endpoint<State>) -> Self;
 
+    /// Set where in the source the endpoint that was added last was declared, for helpers that add
+    /// endpoints inside a closure where `#[track_caller]` can not pass on the location
+    fn located_at(self, _location: &'static Location<'static>) -> Self {
+        self
+    }
+
+    /// Add a metadata value to this route and all routes below it, a value replaces inherited
+    /// metadata of the same type
+    fn meta<T: Any + Send + Sync>(self, value: T) -> Self;
+
     /// Make this a named route
     fn name(self, name: &str) -> Self;
+
+    /// Name this route after a typed route declared with the `typed_routes!` macro, typed names
+    /// are not prefixed with namespaces so they resolve with the name they were declared with
+    fn typed_name<R: TypedRoute>(self) -> Self {
+        self.name(R::NAME)
+    }
+
+    /// Prefix the names of this route and all routes below it with a namespace, nested namespaces
+    /// are separated by dots
+    fn namespace(self, namespace: &str) -> Self;
 }
 
 /// Some extension methods for the routebuilder to make the routing dsl a bit nicer
 pub trait RouteBuilderExt<State: Clone + Send + Sync + 'static>: RouteBuilder<State> {
+    /// Add a path parameter segment with a set of sub-routes. The parameter is parsed as `T` before
+    /// the endpoints below it are called, endpoints can read the parsed value using
+    /// `TypedParamExt::typed_param`. Requests with values that can not be parsed get a
+    /// 400 Bad Request response.
+    fn param<T>(self, name: &str, routes: impl FnOnce(Self) -> Self) -> Self
+    where
+        T: FromStr + Send + Sync + 'static,
+    {
+        self.at(&format!(":{}", name), |route| {
+            route.with(ParseParam::<T>::new(name), routes)
+        })
+    }
+
+    /// Add CORS headers to the responses of a set of sub-routes and answer preflight requests for
+    /// their paths, a nested policy replaces this policy for the routes below it
+    fn cors<R: FnOnce(Self) -> Self>(self, policy: CorsPolicy, routes: R) -> Self {
+        let policy = policy.with_unique_id();
+        self.with(CorsMiddleware::new(policy.clone()), |route| {
+            routes(route.meta(policy))
+        })
+    }
+
+    /// Set the trailing slash policy for this route and all routes below it, this replaces the
+    /// policy from `RegisterOptions::trailing_slash`
+    fn trailing_slash(self, policy: TrailingSlash) -> Self {
+        self.meta(policy)
+    }
+
     /// Add an HTTP GET endpoint
+    #[track_caller]
     fn get(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Get, endpoint)
     }
 
     /// Add an HTTP HEAD endpoint
+    #[track_caller]
     fn head(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Head, endpoint)
     }
 
     /// Add an HTTP PUT endpoint
+    #[track_caller]
     fn put(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Put, endpoint)
     }
 
     /// Add an HTTP POST endpoint
+    #[track_caller]
     fn post(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Post, endpoint)
     }
 
     /// Add an HTTP DELETE endpoint
+    #[track_caller]
     fn delete(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Delete, endpoint)
     }
 
     /// Add an HTTP OPTIONS endpoint
+    #[track_caller]
     fn options(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Options, endpoint)
     }
 
     /// Add an HTTP CONNECT endpoint
+    #[track_caller]
     fn connect(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Connect, endpoint)
     }
 
     /// Add an HTTP PATCH endpoint
+    #[track_caller]
     fn patch(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Patch, endpoint)
     }
 
     /// Add an HTTP TRACE endpoint
+    #[track_caller]
     fn trace(self, endpoint: impl Endpoint<State>) -> Self {
         self.method(Method::Trace, endpoint)
     }
+
+    /// Add a named HTTP GET endpoint
+    #[track_caller]
+    fn get_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Get, name, endpoint)
+    }
+
+    /// Add a named HTTP HEAD endpoint
+    #[track_caller]
+    fn head_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Head, name, endpoint)
+    }
+
+    /// Add a named HTTP PUT endpoint
+    #[track_caller]
+    fn put_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Put, name, endpoint)
+    }
+
+    /// Add a named HTTP POST endpoint
+    #[track_caller]
+    fn post_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Post, name, endpoint)
+    }
+
+    /// Add a named HTTP DELETE endpoint
+    #[track_caller]
+    fn delete_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Delete, name, endpoint)
+    }
+
+    /// Add a named HTTP OPTIONS endpoint
+    #[track_caller]
+    fn options_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Options, name, endpoint)
+    }
+
+    /// Add a named HTTP CONNECT endpoint
+    #[track_caller]
+    fn connect_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Connect, name, endpoint)
+    }
+
+    /// Add a named HTTP PATCH endpoint
+    #[track_caller]
+    fn patch_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Patch, name, endpoint)
+    }
+
+    /// Add a named HTTP TRACE endpoint
+    #[track_caller]
+    fn trace_named(self, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        self.method_named(Method::Trace, name, endpoint)
+    }
 }
 
 impl<State: Clone + Send + Sync + 'static, R: RouteBuilder<State>> RouteBuilderExt<State> for R {}
+
+/// Extension methods for serving and documenting the route tree itself, these need access to the
+/// whole tree when it is registered so they are only available on route segments
+pub trait RouteDocsExt<State: Clone + Send + Sync + 'static>: RouteBuilder<State> {
+    /// Serve the names, paths and parameters of all named routes in the route tree as json on
+    /// HTTP GET
+    fn serve_route_table(self) -> Self;
+
+    /// Serve an OpenAPI 3 document for the whole route tree as json on HTTP GET, this endpoint is
+    /// left out of the document
+    fn serve_openapi(self, info: Info) -> Self;
+
+    /// Add OpenAPI documentation to the endpoint that was added last
+    fn document(self, operation: Operation) -> Self;
+}
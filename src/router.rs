@@ -1,8 +1,21 @@
 //! The router trait and its implementation on tide::Server connect the RouteBuilder to tide and
 //! allows you to call register on a tide::Server with a fluent route tree
 
-use crate::routesegment::{Route, RouteDescriptor, RouteSegment};
-use crate::{util::ArcMiddleware, Result};
+use crate::constraint::{ConstrainedRoute, ConstraintDispatch};
+use crate::cors::{self, CorsPolicy};
+use crate::error::{Conflict, Lint, RouteError};
+use crate::metadata::{Metadata, MetadataMiddleware};
+use crate::methods;
+use crate::openapi::OpenApiEndpoint;
+use crate::path::Path;
+use crate::reverse_router::{BaseUrl, ReverseRouterMiddleware, RouteTableEndpoint};
+use crate::routesegment::{Handler, HandlerEndpoint, Route, RouteDescriptor, RouteSegment};
+use crate::trailing_slash::{self, TrailingSlash};
+use crate::util::{ArcMiddleware, BoxedEndpoint, SharedEndpoint};
+use crate::Result;
+use std::cmp::Ordering;
+use std::panic::Location;
+use std::sync::Arc;
 use tide::{http::Method, Endpoint};
 
 /// A router is any component where routes can be registered on like a tide::Server
@@ -18,14 +31,214 @@ pub trait Router<State: Clone + Send + Sync + 'static>: Sized {
 
     /// Register all routes from a RouteBuilder on the `Router`
     fn register(&mut self, builder: Result<RouteSegment<State>>) -> Result<&mut Self> {
+        self.register_with(builder, RegisterOptions::default())
+    }
+
+    /// Register all routes from a RouteBuilder on the `Router` using the specified options
+    fn register_with(
+        &mut self,
+        builder: Result<RouteSegment<State>>,
+        options: RegisterOptions,
+    ) -> Result<&mut Self> {
+        let segment = builder?;
+
+        // Make sure route names are valid before registering anything
+        let mut reverse_router = segment.reverse_router()?;
+        if let Some(base_url) = options.base_url {
+            reverse_router.set_base_url(base_url);
+        }
+        let reverse_router = Arc::new(reverse_router);
+
+        if options.log_routes {
+            log::info!("Registering routes:\n{}", segment.route_table());
+        }
+
+        let route_infos = segment.routes();
+        let mut routes = segment.build();
+
+        if options.allow_overrides {
+            remove_overridden(&mut routes);
+        } else {
+            let conflicts = find_conflicts(&routes);
+            if !conflicts.is_empty() {
+                return Err(RouteError::Conflicts(conflicts).into());
+            }
+        }
+
+        if options.strict {
+            let handlers: Vec<_> = routes
+                .iter()
+                .filter_map(handler_key)
+                .map(|(path, method, _)| (path, method))
+                .collect();
+
+            let lints = find_lints(&handlers);
+            if !lints.is_empty() {
+                return Err(RouteError::Lints(lints).into());
+            }
+        }
+
+        // Endpoints share their middleware where they can, so synthesized endpoints can tell which
+        // middleware all endpoints of a path have in common
+        let reverse_router_ware =
+            ArcMiddleware::new(ReverseRouterMiddleware::new(reverse_router.clone()));
+        let mut metadata_wares: Vec<(Metadata, ArcMiddleware<State>)> = Vec::new();
+
+        let mut handlers: Vec<ConstrainedRoute<State>> = Vec::new();
+        let mut cors_policies: Vec<(Path, CorsPolicy)> = Vec::new();
+        let mut slash_policies: Vec<(Path, TrailingSlash)> = Vec::new();
+
         for RouteDescriptor {
             path,
-            middleware,
+            mut middleware,
+            metadata,
             route,
-        } in builder?.build()
+        } in routes
         {
-            if let Route::Handler(method, endpoint) = route {
-                self.register_endpoint(&path.to_string(), method, &middleware, endpoint)
+            let cors_policy = metadata.get::<CorsPolicy>().cloned();
+            let slash_policy = metadata
+                .get::<TrailingSlash>()
+                .copied()
+                .unwrap_or(options.trailing_slash);
+            if !metadata.is_empty() {
+                let ware = match metadata_wares
+                    .iter()
+                    .find(|(existing, _)| existing.is_same(&metadata))
+                {
+                    Some((_, ware)) => ware.clone(),
+                    None => {
+                        let ware = ArcMiddleware::new(MetadataMiddleware::new(metadata.clone()));
+                        metadata_wares.push((metadata, ware.clone()));
+                        ware
+                    }
+                };
+                middleware.insert(0, ware);
+            }
+            if options.reverse_router {
+                middleware.insert(0, reverse_router_ware.clone());
+            }
+
+            if let Route::Handler(Handler {
+                method, endpoint, ..
+            }) = route
+            {
+                let endpoint = match endpoint {
+                    HandlerEndpoint::Endpoint(endpoint) => endpoint,
+                    HandlerEndpoint::RouteTable => {
+                        BoxedEndpoint::new(RouteTableEndpoint::new(&reverse_router)?)
+                    }
+                    HandlerEndpoint::OpenApi(info) => {
+                        BoxedEndpoint::new(OpenApiEndpoint::new(&info, &route_infos)?)
+                    }
+                };
+
+                if let Some(policy) = cors_policy {
+                    cors_policies.push((path.clone(), policy));
+                }
+                slash_policies.push((path.clone(), slash_policy));
+
+                handlers.push(ConstrainedRoute {
+                    path,
+                    method,
+                    middleware,
+                    endpoint,
+                    fallback: false,
+                });
+            }
+        }
+
+        // Methods are synthesized before the trailing slash alternatives so the other form of a
+        // path shares the synthesized endpoints
+        cors::preflight(&mut handlers, cors_policies);
+        if options.synthesize_methods {
+            handlers = methods::synthesize(handlers);
+        }
+        handlers = trailing_slash::alternatives(handlers, slash_policies);
+
+        // Routes that only differ in parameter names and constraints are the same route for tide,
+        // these are grouped by their shape
+        let mut groups: Vec<(String, Vec<ConstrainedRoute<State>>)> = Vec::new();
+        for route in handlers {
+            let shape = route.path.shape();
+            match groups.iter_mut().find(|(existing, _)| *existing == shape) {
+                Some((_, group)) => group.push(route),
+                None => groups.push((shape, vec![route])),
+            }
+        }
+
+        // Tide picks between overlapping shapes without looking at constraints and tries routes
+        // for a method before catch-all routes, so groups are joined with every group they overlap
+        // into components
+        let mut components: Vec<usize> = (0..groups.len()).collect();
+        for index in 0..groups.len() {
+            for other in index + 1..groups.len() {
+                let overlapping = groups[index].1.iter().any(|route| {
+                    groups[other]
+                        .1
+                        .iter()
+                        .any(|other| route.path.overlaps(&other.path))
+                });
+
+                if overlapping {
+                    let (from, to) = (components[other], components[index]);
+                    for component in components.iter_mut().filter(|id| **id == from) {
+                        *component = to;
+                    }
+                }
+            }
+        }
+
+        let mut groups: Vec<(usize, Vec<ConstrainedRoute<State>>)> = components
+            .into_iter()
+            .zip(groups.into_iter().map(|(_, group)| group))
+            .collect();
+
+        while !groups.is_empty() {
+            let id = groups[0].0;
+            let (component, rest) = groups.into_iter().partition(|(other, _)| *other == id);
+            groups = rest;
+
+            let dispatched = component.len() > 1
+                || component.iter().any(|(_, group)| {
+                    group.iter().any(|route| route.path != group[0].path)
+                        || group[0].path.is_constrained()
+                });
+
+            if !dispatched {
+                for route in component.into_iter().flat_map(|(_, group)| group) {
+                    self.register_endpoint(
+                        &route.path.to_string(),
+                        route.method,
+                        &route.middleware,
+                        route.endpoint,
+                    );
+                }
+                continue;
+            }
+
+            // Overlapping shapes and shapes with constraints or different paths are dispatched by
+            // trying the paths in order of specificity, the first declared route wins between
+            // equally specific routes. This is the precedence `find_lints` reports. The dispatch is
+            // registered for each shape and method so tide doesn't prefer a route of another shape.
+            let patterns: Vec<String> = component
+                .iter()
+                .map(|(_, group)| group[0].path.dispatch_pattern())
+                .collect();
+            let routes: Vec<ConstrainedRoute<State>> =
+                component.into_iter().flat_map(|(_, group)| group).collect();
+
+            let mut methods: Vec<Option<Method>> = Vec::new();
+            for route in &routes {
+                if !methods.contains(&route.method) {
+                    methods.push(route.method);
+                }
+            }
+
+            let dispatch = SharedEndpoint::new(BoxedEndpoint::new(ConstraintDispatch::new(routes)));
+            for pattern in &patterns {
+                for method in &methods {
+                    self.register_endpoint(pattern, *method, &[], dispatch.clone());
+                }
             }
         }
 
@@ -33,6 +246,192 @@ pub trait Router<State: Clone + Send + Sync + 'static>: Sized {
     }
 }
 
+/// Options for registering a route tree on a `Router`
+#[derive(Debug, Clone, Default)]
+pub struct RegisterOptions {
+    allow_overrides: bool,
+    strict: bool,
+    log_routes: bool,
+    reverse_router: bool,
+    base_url: Option<BaseUrl>,
+    synthesize_methods: bool,
+    trailing_slash: TrailingSlash,
+}
+
+impl RegisterOptions {
+    /// Construct the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow endpoints to replace earlier endpoints for the same path and method instead of
+    /// failing with a conflict error, the endpoint that comes last in the route tree is kept.
+    /// This is useful for replacing endpoints with test doubles.
+    pub fn allow_overrides(mut self) -> Self {
+        self.allow_overrides = true;
+        self
+    }
+
+    /// Fail registering when routes overlap or can not be reached instead of leaving it to tide to
+    /// pick a route, see `RouteSegment::lint`
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Answer methods that have no endpoint instead of leaving them to tide. Paths without a
+    /// catch-all endpoint respond to OPTIONS with an `Allow` header listing their methods, to HEAD
+    /// with the headers of their GET endpoint and to other methods with a 405 Method Not Allowed.
+    pub fn synthesize_methods(mut self) -> Self {
+        self.synthesize_methods = true;
+        self
+    }
+
+    /// Set the trailing slash policy for the whole route tree, subtrees can replace it using
+    /// `RouteBuilderExt::trailing_slash`. The default policy is `TrailingSlash::Strict`.
+    pub fn trailing_slash(mut self, policy: TrailingSlash) -> Self {
+        self.trailing_slash = policy;
+        self
+    }
+
+    /// Log a table of all routes at info level when registering
+    pub fn log_routes(mut self) -> Self {
+        self.log_routes = true;
+        self
+    }
+
+    /// Make the reverse router for the route tree available to all endpoints through request
+    /// extensions, use the `UrlFor` extension trait to resolve named routes from a request
+    pub fn with_reverse_router(mut self) -> Self {
+        self.reverse_router = true;
+        self
+    }
+
+    /// Make the reverse router available to endpoints like `with_reverse_router` and configure
+    /// the base url it uses for resolving absolute urls
+    pub fn with_base_url(mut self, base_url: BaseUrl) -> Self {
+        self.reverse_router = true;
+        self.base_url = Some(base_url);
+        self
+    }
+}
+
+/// Returns the path, method and declaration location of a handler route
+fn handler_key<State>(
+    route: &RouteDescriptor<State>,
+) -> Option<(&Path, Option<Method>, &'static Location<'static>)> {
+    match &route.route {
+        Route::Handler(handler) => Some((&route.path, handler.method, handler.location)),
+        _ => None,
+    }
+}
+
+/// Find all paths and methods that more than one endpoint is registered for
+fn find_conflicts<State>(routes: &[RouteDescriptor<State>]) -> Vec<Conflict> {
+    let mut conflicts: Vec<(&Path, Conflict)> = Vec::new();
+
+    for (path, method, location) in routes.iter().filter_map(handler_key) {
+        match conflicts
+            .iter_mut()
+            .find(|(existing, conflict)| *existing == path && conflict.method == method)
+        {
+            Some((_, conflict)) => conflict.locations.push(location),
+            None => conflicts.push((
+                path,
+                Conflict {
+                    path: path.describe(),
+                    method,
+                    locations: vec![location],
+                },
+            )),
+        }
+    }
+
+    conflicts
+        .into_iter()
+        .map(|(_, conflict)| conflict)
+        .filter(|conflict| conflict.locations.len() > 1)
+        .collect()
+}
+
+/// Find paths that overlap a more specific path and endpoints that are shadowed by an endpoint that
+/// handles the same method on a path that only differs in parameter names, following the order in
+/// which overlapping routes are dispatched
+pub(crate) fn find_lints(handlers: &[(&Path, Option<Method>)]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+
+    let mut paths: Vec<&Path> = Vec::new();
+    for (path, _) in handlers {
+        if !paths.contains(path) {
+            paths.push(path);
+        }
+    }
+
+    for (index, path) in paths.iter().enumerate() {
+        for other in &paths[index + 1..] {
+            if path.shape() != other.shape() && path.overlaps(other) {
+                let (general, specific) = match path.cmp_specificity(other) {
+                    Ordering::Less => (path, other),
+                    _ => (other, path),
+                };
+                lints.push(Lint::Overlap {
+                    path: general.describe(),
+                    overlapping: specific.describe(),
+                });
+            }
+        }
+    }
+
+    for (index, (path, method)) in handlers.iter().enumerate() {
+        if path.is_constrained() {
+            continue;
+        }
+
+        // The first declared path that handles the method is dispatched to, catch-alls handle
+        // every method and GET handles HEAD
+        let shadowed_by = handlers[..index].iter().find(|(other, other_method)| {
+            let handles = match (other_method, method) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(other_method), Some(method)) => {
+                    other_method == method
+                        || (*method == Method::Head && *other_method == Method::Get)
+                }
+            };
+
+            handles && other != path && !other.is_constrained() && other.shape() == path.shape()
+        });
+
+        if let Some((shadowed_by, _)) = shadowed_by {
+            lints.push(Lint::Unreachable {
+                path: path.describe(),
+                method: *method,
+                shadowed_by: shadowed_by.describe(),
+            });
+        }
+    }
+
+    lints
+}
+
+/// Remove all endpoints that are replaced by a later endpoint for the same path and method
+fn remove_overridden<State>(routes: &mut Vec<RouteDescriptor<State>>) {
+    let mut index = 0;
+    while index < routes.len() {
+        let key = handler_key(&routes[index]).map(|(path, method, _)| (path, method));
+        let overridden = key.is_some()
+            && routes[index + 1..]
+                .iter()
+                .any(|later| handler_key(later).map(|(path, method, _)| (path, method)) == key);
+
+        if overridden {
+            routes.remove(index);
+        } else {
+            index += 1;
+        }
+    }
+}
+
 impl<State: Clone + Send + Sync + 'static> Router<State> for tide::Server<State> {
     fn register_endpoint(
         &mut self,
@@ -41,6 +440,8 @@ impl<State: Clone + Send + Sync + 'static> Router<State> for tide::Server<State>
         middleware: &[ArcMiddleware<State>],
         endpoint: impl Endpoint<State>,
     ) {
+        // Tide keeps middleware on the route handle, so the endpoint has to be added to the same
+        // handle the middleware was added to
         let mut route = self.at(path);
         for ware in middleware {
             route.with(ware.clone());
@@ -48,8 +449,803 @@ impl<State: Clone + Send + Sync + 'static> Router<State> for tide::Server<State>
 
         // if method is specified then register this method, otherwise register endpoint as a catch_all
         match method {
-            Some(method) => self.at(path).method(method, endpoint),
-            None => self.at(path).all(endpoint),
+            Some(method) => route.method(method, endpoint),
+            None => route.all(endpoint),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::{Lint, RouteError};
+    use crate::fs::ServeFs;
+    use crate::openapi::Info;
+    use crate::params;
+    use crate::prelude::*;
+    use crate::reverse_router::BaseUrl;
+    use crate::test_util::{call, endpoint, other_endpoint, respond};
+    use tide::http::{self, Url};
+    use tide::{utils::async_trait, Middleware, Next, Request, Response, StatusCode};
+
+    /// Middleware that records its name in the request so the endpoint can report it
+    #[derive(Debug)]
+    struct Trace(&'static str);
+
+    #[async_trait]
+    impl Middleware<()> for Trace {
+        async fn handle(&self, mut request: Request<()>, next: Next<'_, ()>) -> tide::Result {
+            let mut trace: Vec<&'static str> = request.ext().cloned().unwrap_or_default();
+            trace.push(self.0);
+            request.set_ext(trace);
+
+            Ok(next.run(request).await)
+        }
+    }
+
+    /// Middleware that refuses every request without calling the endpoint
+    #[derive(Debug)]
+    struct Deny;
+
+    #[async_trait]
+    impl Middleware<()> for Deny {
+        async fn handle(&self, _: Request<()>, _: Next<'_, ()>) -> tide::Result {
+            Ok(Response::new(StatusCode::Unauthorized))
+        }
+    }
+
+    async fn report_trace(request: Request<()>) -> tide::Result {
+        let trace: Vec<&'static str> = request.ext().cloned().unwrap_or_default();
+        Ok(trace.join(",").into())
+    }
+
+    #[async_std::test]
+    async fn should_run_middleware_for_endpoint() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().with(Trace("auth"), |r| r.get(report_trace)))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/").await,
+            (StatusCode::Ok, "auth".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_run_middleware_in_declaration_order() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().with(Trace("first"), |r| {
+                r.at("path", |r| {
+                    r.with(Trace("second"), |r| {
+                        r.with(Trace("third"), |r| r.get(report_trace))
+                    })
+                })
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/path").await,
+            (StatusCode::Ok, "first,second,third".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_only_run_middleware_for_endpoints_in_its_subtree() {
+        let mut server = tide::Server::new();
+        server
+            .register(
+                root()
+                    .get(report_trace)
+                    .with(Trace("auth"), |r| r.post(report_trace))
+                    .at("open", |r| r.all(report_trace)),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/").await,
+            (StatusCode::Ok, "".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Post, "/").await,
+            (StatusCode::Ok, "auth".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Delete, "/open").await,
+            (StatusCode::Ok, "".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_run_middleware_for_catch_all_endpoint() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().with(Trace("auth"), |r| r.all(report_trace)))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Put, "/").await,
+            (StatusCode::Ok, "auth".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_let_middleware_short_circuit_endpoint() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().with(Deny, |r| r.get(report_trace)))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/").await.0,
+            StatusCode::Unauthorized
+        );
+    }
+
+    fn conflicts(error: tide::Error) -> Vec<(String, Option<Method>, usize)> {
+        match error.downcast_ref::<RouteError>() {
+            Some(RouteError::Conflicts(conflicts)) => conflicts
+                .iter()
+                .map(|conflict| {
+                    (
+                        conflict.path.clone(),
+                        conflict.method,
+                        conflict.locations.len(),
+                    )
+                })
+                .collect(),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_report_duplicate_endpoint_in_segment() {
+        let mut server = tide::Server::new();
+        let error = server
+            .register(root().get(endpoint).get(other_endpoint))
+            .unwrap_err();
+
+        assert_eq!(
+            conflicts(error),
+            vec![("/".to_string(), Some(Method::Get), 2)]
+        );
+    }
+
+    #[test]
+    fn should_report_location_of_serve_dir_call() {
+        let mut server = tide::Server::new();
+        let line = line!() + 1;
+        let routes = root().serve_dir("src").unwrap().serve_dir("src").unwrap();
+        let error = server.register(routes).unwrap_err();
+
+        match error.downcast_ref::<RouteError>() {
+            Some(RouteError::Conflicts(conflicts)) => {
+                assert_eq!(conflicts[0].locations[0].file(), file!());
+                assert_eq!(conflicts[0].locations[0].line(), line);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_report_duplicate_endpoints_in_sibling_branches() {
+        let mut server = tide::Server::new();
+        let error = server
+            .register(
+                root()
+                    .at("x", |r| r.get(endpoint).all(endpoint))
+                    .at("x", |r| r.get(other_endpoint).all(other_endpoint)),
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            conflicts(error),
+            vec![
+                ("/x".to_string(), Some(Method::Get), 2),
+                ("/x".to_string(), None, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_where_duplicates_are_declared() {
+        let mut server = tide::Server::new();
+        let error = server
+            .register(root().get(endpoint).with(Deny, |r| r.get(other_endpoint)))
+            .unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.contains("GET / declared at src/router.rs:"));
+        assert_eq!(message.matches("src/router.rs:").count(), 2);
+    }
+
+    #[test]
+    fn should_not_report_different_methods_on_same_path() {
+        let mut server = tide::Server::new();
+        let result = server.register(
+            root()
+                .get(endpoint)
+                .post(endpoint)
+                .all(endpoint)
+                .at("x", |r| r.get(endpoint)),
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[async_std::test]
+    async fn should_use_last_endpoint_when_overrides_are_allowed() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root()
+                    .at("x", |r| r.get(endpoint))
+                    .at("x", |r| r.get(other_endpoint)),
+                RegisterOptions::new().allow_overrides(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/x").await,
+            (StatusCode::Ok, "second".to_string())
+        );
+    }
+
+    async fn link_to_article(request: Request<()>) -> tide::Result {
+        Ok(request
+            .url_for("article", params! {"id" => request.param("id")?})?
+            .into())
+    }
+
+    #[async_std::test]
+    async fn should_resolve_urls_from_request() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles/:id", |r| {
+                    r.name("article").at("link", |r| {
+                        r.with(Trace("auth"), |r| r.get(link_to_article))
+                    })
+                }),
+                RegisterOptions::new().with_reverse_router(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/12/link").await,
+            (StatusCode::Ok, "/articles/12".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_fail_resolving_urls_without_reverse_router() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("articles/:id", |r| r.name("article").get(link_to_article)))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/12").await.0,
+            StatusCode::InternalServerError
+        );
+    }
+
+    #[async_std::test]
+    async fn should_resolve_absolute_urls_from_request() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| {
+                    r.name("articles").post(|request: Request<()>| async move {
+                        let location = request.absolute_url_for("articles", params! {})?;
+                        Ok(Response::builder(StatusCode::Created)
+                            .header("Location", location)
+                            .build())
+                    })
+                }),
+                RegisterOptions::new().with_base_url(BaseUrl::new("https", "example.com")),
+            )
+            .unwrap();
+
+        let url = Url::parse("http://localhost/articles").unwrap();
+        let response: http::Response = server
+            .respond(http::Request::new(Method::Post, url))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::Created);
+        assert_eq!(response["Location"], "https://example.com/articles");
+    }
+
+    #[async_std::test]
+    async fn should_serve_route_table() {
+        let mut server = tide::Server::new();
+        server
+            .register(
+                root()
+                    .name("root")
+                    .at("articles/:id", |r| r.name("article").get(endpoint))
+                    .at("files/*path", |r| r.name("file").get(endpoint))
+                    .at("routes.json", |r| r.serve_route_table()),
+            )
+            .unwrap();
+
+        let (status, body) = call(&server, Method::Get, "/routes.json").await;
+
+        assert_eq!(status, StatusCode::Ok);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({
+                "article": {"path": "/articles/:id", "params": ["id"]},
+                "file": {"path": "/files/*path", "params": ["path"]},
+                "root": {"path": "/", "params": []}
+            })
+        );
+    }
+
+    async fn report_param(request: Request<()>) -> tide::Result {
+        let (name, value) = match request.param("id") {
+            Ok(id) => ("id", id),
+            Err(_) => ("slug", request.param("slug")?),
         };
+        Ok(format!("{}={}", name, value).into())
+    }
+
+    #[async_std::test]
+    async fn should_pass_unnamed_wildcard_as_path_param() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("files/*", |r| {
+                r.get(|request: Request<()>| async move { Ok(request.param("path")?.to_string()) })
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/files/images/logo.png").await,
+            (StatusCode::Ok, "images/logo.png".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_fall_through_to_sibling_when_constraint_fails() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("articles", |r| {
+                r.at_matching(":id", Constraint::Numeric, |r| r.get(report_param))
+                    .at(":slug", |r| r.get(report_param))
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/12").await,
+            (StatusCode::Ok, "id=12".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/articles/first-post").await,
+            (StatusCode::Ok, "slug=first-post".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_prefer_constrained_catch_all_over_sibling() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("articles", |r| {
+                r.at_matching(":id", Constraint::Numeric, |r| r.all(endpoint))
+                    .at(":slug", |r| r.get(other_endpoint))
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/12").await,
+            (StatusCode::Ok, "first".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/articles/first-post").await,
+            (StatusCode::Ok, "second".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_prefer_constrained_route_over_overlapping_wildcard() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("articles", |r| {
+                r.at(r":id(\d+)", |r| r.get(endpoint))
+                    .at("*rest", |r| r.get(other_endpoint))
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/5").await,
+            (StatusCode::Ok, "first".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/articles/abc").await,
+            (StatusCode::Ok, "second".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/articles/5/comments").await,
+            (StatusCode::Ok, "second".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Post, "/articles/5").await.0,
+            StatusCode::MethodNotAllowed
+        );
+    }
+
+    #[async_std::test]
+    async fn should_keep_errors_of_constrained_routes_for_middleware() {
+        let mut server = tide::Server::new();
+        server.with(tide::utils::After(|mut response: Response| async move {
+            if let Some(error) = response.error() {
+                let message = error.to_string();
+                response.set_body(message);
+            }
+            Ok(response)
+        }));
+        server
+            .register(root().at("articles", |r| {
+                r.at_matching(":id", Constraint::Numeric, |r| {
+                    r.get(|_| async {
+                        Err::<Response, _>(tide::Error::from_str(StatusCode::Conflict, "locked"))
+                    })
+                })
+                .at(":slug", |r| r.get(report_param))
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/12").await,
+            (StatusCode::Conflict, "locked".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_not_match_when_inline_constraint_fails() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at(r"articles/:id(\d+)", |r| {
+                r.with(Trace("auth"), |r| r.get(report_trace))
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/12").await,
+            (StatusCode::Ok, "auth".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/articles/first-post").await.0,
+            StatusCode::NotFound
+        );
+    }
+
+    #[test]
+    fn should_report_duplicate_endpoints_with_same_constraints() {
+        let mut server = tide::Server::new();
+        let error = server
+            .register(
+                root()
+                    .at(r"x/:id(\d+)", |r| r.get(endpoint))
+                    .at(r"x/:id(\d+)", |r| r.get(other_endpoint))
+                    .at("x/:id", |r| r.get(endpoint)),
+            )
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains(r"GET /x/:id(\d+) declared at src/router.rs:"));
+        assert_eq!(
+            conflicts(error),
+            vec![(r"/x/:id(\d+)".to_string(), Some(Method::Get), 2)]
+        );
+    }
+
+    #[test]
+    fn should_lint_overlapping_and_unreachable_routes() {
+        let lints = root::<()>()
+            .at("articles", |r| {
+                r.at("new", |r| r.get(endpoint))
+                    .at(":id", |r| r.get(endpoint))
+                    .at(":slug", |r| r.get(endpoint).post(endpoint))
+            })
+            .at("files/*path", |r| r.get(endpoint))
+            .at("files/index.html", |r| r.get(endpoint))
+            .unwrap()
+            .lint();
+
+        assert_eq!(
+            lints,
+            vec![
+                Lint::Overlap {
+                    path: "/articles/:id".to_string(),
+                    overlapping: "/articles/new".to_string()
+                },
+                Lint::Overlap {
+                    path: "/articles/:slug".to_string(),
+                    overlapping: "/articles/new".to_string()
+                },
+                Lint::Overlap {
+                    path: "/files/*path".to_string(),
+                    overlapping: "/files/index.html".to_string()
+                },
+                Lint::Unreachable {
+                    path: "/articles/:slug".to_string(),
+                    method: Some(Method::Get),
+                    shadowed_by: "/articles/:id".to_string()
+                },
+            ]
+        );
+    }
+
+    #[async_std::test]
+    async fn should_lint_the_route_that_answers_requests() {
+        let routes = || {
+            root::<()>()
+                .at("files", |r| r.at("index.html", |r| r.all(endpoint)))
+                .at("files/*path", |r| r.get(other_endpoint))
+                .at("articles/:id", |r| r.all(endpoint))
+                .at("articles/:slug", |r| r.head(other_endpoint))
+        };
+
+        assert_eq!(
+            routes().unwrap().lint(),
+            vec![
+                Lint::Overlap {
+                    path: "/files/*path".to_string(),
+                    overlapping: "/files/index.html".to_string()
+                },
+                Lint::Unreachable {
+                    path: "/articles/:slug".to_string(),
+                    method: Some(Method::Head),
+                    shadowed_by: "/articles/:id".to_string()
+                },
+            ]
+        );
+
+        let mut server = tide::Server::new();
+        server.register(routes()).unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/files/index.html").await,
+            (StatusCode::Ok, "first".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Head, "/articles/12").await,
+            (StatusCode::Ok, "first".to_string())
+        );
+    }
+
+    #[test]
+    fn should_not_lint_routes_separated_by_constraints() {
+        let lints = root::<()>()
+            .at("articles", |r| {
+                r.at(r":id(\d+)", |r| r.get(endpoint))
+                    .at(":slug", |r| r.get(endpoint))
+            })
+            .unwrap()
+            .lint();
+
+        assert_eq!(lints, vec![]);
+    }
+
+    #[test]
+    fn should_fail_registering_ambiguous_routes_in_strict_mode() {
+        let mut server = tide::Server::new();
+        let error = server
+            .register_with(
+                root().at("articles", |r| {
+                    r.at("new", |r| r.get(endpoint))
+                        .at(":id", |r| r.get(endpoint))
+                }),
+                RegisterOptions::new().strict(),
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "ambiguous routes in route tree:\n  /articles/:id overlaps /articles/new, /articles/new takes precedence"
+        );
+    }
+
+    #[async_std::test]
+    async fn should_use_first_of_routes_that_differ_in_param_names() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("articles", |r| {
+                r.at(":id", |r| r.get(report_param))
+                    .at(":slug", |r| r.get(report_param).post(report_param))
+            }))
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Get, "/articles/first-post").await,
+            (StatusCode::Ok, "id=first-post".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Post, "/articles/first-post").await,
+            (StatusCode::Ok, "slug=first-post".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_serve_openapi_document() {
+        let mut server = tide::Server::new();
+        server
+            .register(
+                root()
+                    .at("articles/:id", |r| r.get_named("article", endpoint))
+                    .at("openapi.json", |r| {
+                        r.serve_openapi(Info::new("Articles", "1.0"))
+                    }),
+            )
+            .unwrap();
+
+        let (status, body) = call(&server, Method::Get, "/openapi.json").await;
+        let document: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(status, StatusCode::Ok);
+        assert_eq!(document["info"]["title"], "Articles");
+        assert_eq!(
+            document["paths"]["/articles/{id}"]["get"]["operationId"],
+            "article"
+        );
+    }
+
+    #[async_std::test]
+    async fn should_synthesize_missing_methods() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| r.get(endpoint).post(other_endpoint)),
+                RegisterOptions::new().synthesize_methods(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Delete, "/articles").await.0,
+            StatusCode::MethodNotAllowed
+        );
+        assert_eq!(
+            respond(&server, Method::Delete, "/articles").await["Allow"],
+            "GET, HEAD, OPTIONS, POST"
+        );
+        assert_eq!(
+            call(&server, Method::Options, "/articles").await.0,
+            StatusCode::NoContent
+        );
+        assert_eq!(
+            respond(&server, Method::Options, "/articles").await["Allow"],
+            "GET, HEAD, OPTIONS, POST"
+        );
+        // The server leaves out the body but keeps the length of the GET response
+        assert_eq!(
+            respond(&server, Method::Head, "/articles").await.len(),
+            Some(5)
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/articles").await,
+            (StatusCode::Ok, "first".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/missing").await.0,
+            StatusCode::NotFound
+        );
+    }
+
+    #[async_std::test]
+    async fn should_run_middleware_for_synthesized_methods() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| r.with(Deny, |r| r.get(endpoint))),
+                RegisterOptions::new().synthesize_methods(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Options, "/articles").await.0,
+            StatusCode::Unauthorized
+        );
+        assert_eq!(
+            call(&server, Method::Delete, "/articles").await.0,
+            StatusCode::Unauthorized
+        );
+    }
+
+    #[async_std::test]
+    async fn should_only_run_shared_middleware_for_synthesized_methods() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| {
+                    r.with(Deny, |r| r.post(other_endpoint))
+                        .with(Trace("get"), |r| r.get(endpoint))
+                }),
+                RegisterOptions::new().synthesize_methods(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Post, "/articles").await.0,
+            StatusCode::Unauthorized
+        );
+        assert_eq!(
+            call(&server, Method::Options, "/articles").await.0,
+            StatusCode::NoContent
+        );
+        assert_eq!(
+            call(&server, Method::Delete, "/articles").await.0,
+            StatusCode::MethodNotAllowed
+        );
+    }
+
+    #[async_std::test]
+    async fn should_answer_options_for_redirected_path() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| r.get(endpoint)),
+                RegisterOptions::new()
+                    .synthesize_methods()
+                    .trailing_slash(TrailingSlash::Redirect),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Options, "/articles/").await.0,
+            StatusCode::NoContent
+        );
+        assert_eq!(
+            respond(&server, Method::Options, "/articles/").await["Allow"],
+            "GET, HEAD, OPTIONS"
+        );
+        assert_eq!(
+            call(&server, Method::Get, "/articles/").await.0,
+            StatusCode::MovedPermanently
+        );
+    }
+
+    #[async_std::test]
+    async fn should_not_synthesize_methods_for_catch_all_routes() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| r.get(endpoint).all(other_endpoint)),
+                RegisterOptions::new().synthesize_methods(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Delete, "/articles").await,
+            (StatusCode::Ok, "second".to_string())
+        );
+    }
+
+    #[async_std::test]
+    async fn should_fall_through_to_sibling_with_synthesized_methods() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| {
+                    r.at_matching(":id", Constraint::Numeric, |r| r.get(report_param))
+                        .at(":slug", |r| r.get(report_param).delete(report_param))
+                }),
+                RegisterOptions::new().synthesize_methods(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            call(&server, Method::Delete, "/articles/12").await,
+            (StatusCode::Ok, "slug=12".to_string())
+        );
+        assert_eq!(
+            call(&server, Method::Post, "/articles/12").await.0,
+            StatusCode::MethodNotAllowed
+        );
+        assert_eq!(
+            respond(&server, Method::Post, "/articles/12").await["Allow"],
+            "GET, HEAD, OPTIONS"
+        );
     }
 }
@@ -1,20 +1,30 @@
+use crate::constraint::Constraint;
+use crate::error::{DuplicateName, Lint, RouteError};
+use crate::listing::{route_table, short_type_name};
+use crate::metadata::Metadata;
+use crate::openapi::{openapi_document, Info, Operation};
 use crate::path::Path;
-use crate::reverse_router::ReverseRouter;
-use crate::routebuilder::RouteBuilder;
+use crate::reverse_router::{ReverseRouter, TypedRoute};
+use crate::routebuilder::{RouteBuilder, RouteDocsExt};
+use crate::router::find_lints;
 use crate::util::{ArcMiddleware, BoxedEndpoint};
 use crate::Result;
-use std::collections::HashMap;
+use std::any::Any;
+use std::panic::Location;
 use tide::http::Method;
 use tide::{Endpoint, Middleware};
 
 /// Start building a route. Returns a RouteSegment for the root of your route
 pub fn root<State>() -> SubRoute<State> {
     Ok(RouteSegment {
-        path: Path::prefix("/"),
+        path: Path::root(),
         middleware: Vec::new(),
         name: None,
+        typed: false,
+        namespace: None,
+        metadata: Metadata::default(),
         branches: Vec::new(),
-        endpoints: HashMap::new(),
+        endpoints: Vec::new(),
     })
 }
 
@@ -27,78 +37,301 @@ pub struct RouteSegment<State> {
     middleware: Vec<ArcMiddleware<State>>,
 
     name: Option<String>,
+    typed: bool,
+    namespace: Option<String>,
+    metadata: Metadata,
     branches: Vec<RouteSegment<State>>,
-    endpoints: HashMap<Option<Method>, BoxedEndpoint<State>>,
+    endpoints: Vec<Handler<State>>,
 }
 
 impl<State: Clone + Send + Sync + 'static> RouteSegment<State> {
-    fn names(&self) -> Vec<RouteDescriptor<State>> {
+    /// Collect all named routes, names are prefixed with the namespaces of the segment and its
+    /// parents separated by dots
+    fn names(&self, namespace: Option<&str>) -> Vec<RouteDescriptor<State>> {
         let path = self.path.clone();
 
+        let namespace = self.namespace(namespace);
+        let qualify = |name: &str| qualify(namespace.as_deref(), name);
+
         let local_name = self
-            .name
-            .clone()
-            .map(|name| RouteDescriptor {
+            .qualified_name(namespace.as_deref())
+            .map(|name| (name, None))
+            .into_iter();
+
+        let endpoint_names = self.endpoints.iter().filter_map(|handler| {
+            let name = handler.name.as_ref()?;
+            Some((qualify(name), handler.method))
+        });
+
+        let local_names: Vec<_> = local_name
+            .chain(endpoint_names)
+            .map(|(name, method)| RouteDescriptor {
                 path: path.clone(),
                 middleware: Vec::new(), // We don't care about middleware for route names
-                route: Route::Name(name),
+                metadata: Metadata::default(),
+                route: Route::Name(name, method),
             })
-            .into_iter();
+            .collect();
 
-        let sub_routes = self.branches.iter().flat_map(RouteSegment::names);
+        let sub_routes = self
+            .branches
+            .iter()
+            .flat_map(|branch| branch.names(namespace.as_deref()));
 
-        local_name.chain(sub_routes).collect()
+        local_names.into_iter().chain(sub_routes).collect()
     }
 
-    /// Construct a reverse router for the paths in the route builder
-    pub fn reverse_router(&self) -> ReverseRouter {
-        let mut routes = ReverseRouter::new();
+    /// The name of the segment prefixed with its namespace, typed route names are used as is
+    fn qualified_name(&self, namespace: Option<&str>) -> Option<String> {
+        let name = self.name.as_ref()?;
 
-        for RouteDescriptor {
-            path,
-            middleware: _,
-            route,
-        } in self.names()
-        {
-            if let Route::Name(name) = route {
-                routes.insert(&name, &path.to_string());
+        if self.typed {
+            Some(name.clone())
+        } else {
+            Some(qualify(namespace, name))
+        }
+    }
+
+    /// The namespace of the segment prefixed with the namespace of its parents
+    fn namespace(&self, parent: Option<&str>) -> Option<String> {
+        match (parent, &self.namespace) {
+            (Some(parent), Some(local)) => Some(format!("{}.{}", parent, local)),
+            (parent, local) => local.clone().or_else(|| parent.map(str::to_string)),
+        }
+    }
+
+    /// Describe every endpoint in the route tree without registering it
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        self.route_infos(None, &Metadata::default())
+    }
+
+    fn route_infos(&self, namespace: Option<&str>, metadata: &Metadata) -> Vec<RouteInfo> {
+        let namespace = self.namespace(namespace);
+        let metadata = self.metadata.inherit(metadata);
+
+        let local_routes = self.endpoints.iter().map(|handler| RouteInfo {
+            path: self.path.clone(),
+            method: handler.method,
+            name: handler
+                .name
+                .as_ref()
+                .map(|name| qualify(namespace.as_deref(), name))
+                .or_else(|| self.qualified_name(namespace.as_deref())),
+            middleware: self
+                .middleware
+                .iter()
+                .map(|ware| ware.name().to_string())
+                .collect(),
+            metadata: metadata.clone(),
+            operation: handler.operation.clone(),
+            location: handler.location,
+        });
+
+        let sub_routes = self
+            .branches
+            .iter()
+            .flat_map(|branch| branch.route_infos(namespace.as_deref(), &metadata));
+
+        local_routes.chain(sub_routes).collect()
+    }
+
+    /// Generate an OpenAPI 3 document for the route tree, see the `openapi` module
+    pub fn openapi(&self, info: &Info) -> serde_json::Value {
+        openapi_document(info, &self.routes())
+    }
+
+    /// Format the endpoints in the route tree as an aligned table, see `listing::route_table`
+    pub fn route_table(&self) -> String {
+        route_table(&self.routes())
+    }
+
+    /// Format the route tree with a line for every `at`, `with` and endpoint, nested the same way
+    /// the tree was built
+    pub fn route_tree(&self) -> String {
+        let mut tree = String::new();
+        self.tree_lines(None, None, 0, &mut tree);
+        tree
+    }
+
+    fn tree_lines(
+        &self,
+        parent: Option<&Self>,
+        namespace: Option<&str>,
+        depth: usize,
+        tree: &mut String,
+    ) {
+        let label = match parent {
+            None => self.path.to_string(),
+            Some(parent) if self.middleware.len() > parent.middleware.len() => {
+                let ware = self.middleware.last().map_or("", |ware| ware.name());
+                format!("with {}", short_type_name(ware))
+            }
+            Some(parent) => {
+                let segments: Vec<String> = self
+                    .path
+                    .iter()
+                    .skip(parent.path.iter().count())
+                    .map(ToString::to_string)
+                    .collect();
+                if segments.is_empty() {
+                    "/".to_string()
+                } else {
+                    segments.join("/")
+                }
+            }
+        };
+
+        let local_namespace = self.namespace.as_ref().and(self.namespace(namespace));
+        let namespace = self.namespace(namespace);
+
+        tree.push_str(&"  ".repeat(depth));
+        tree.push_str(&label);
+        if let Some(local_namespace) = local_namespace {
+            tree.push_str(&format!(" [namespace {}]", local_namespace));
+        }
+        if let Some(name) = self.qualified_name(namespace.as_deref()) {
+            tree.push_str(&format!(" [name {}]", name));
+        }
+        tree.push('\n');
+
+        for handler in &self.endpoints {
+            tree.push_str(&"  ".repeat(depth + 1));
+            match handler.method {
+                Some(method) => tree.push_str(method.as_ref()),
+                None => tree.push_str("ANY"),
+            }
+            if let Some(name) = &handler.name {
+                tree.push(' ');
+                tree.push_str(&qualify(namespace.as_deref(), name));
+            }
+            tree.push('\n');
+        }
+
+        for branch in &self.branches {
+            branch.tree_lines(Some(self), namespace.as_deref(), depth + 1, tree);
+        }
+    }
+
+    /// Construct a reverse router for the paths in the route builder, fails when the same name is
+    /// used for more than one route
+    pub fn reverse_router(&self) -> Result<ReverseRouter> {
+        let names: Vec<(String, Path, Option<Method>)> = self
+            .names(None)
+            .into_iter()
+            .filter_map(|RouteDescriptor { path, route, .. }| match route {
+                Route::Name(name, method) => Some((name, path, method)),
+                _ => None,
+            })
+            .collect();
+
+        let mut duplicates: Vec<DuplicateName> = Vec::new();
+        for (name, path, _) in &names {
+            match duplicates
+                .iter_mut()
+                .find(|duplicate| &duplicate.name == name)
+            {
+                Some(duplicate) => duplicate.paths.push(path.to_string()),
+                None => duplicates.push(DuplicateName {
+                    name: name.clone(),
+                    paths: vec![path.to_string()],
+                }),
             }
         }
+        duplicates.retain(|duplicate| duplicate.paths.len() > 1);
+
+        if !duplicates.is_empty() {
+            return Err(RouteError::DuplicateNames(duplicates).into());
+        }
+
+        let mut routes = ReverseRouter::new();
+        for (name, path, method) in names {
+            routes.insert_route(&name, path, method)?;
+        }
+
+        Ok(routes)
+    }
+
+    /// Report paths that overlap more specific paths and endpoints that can not be reached because
+    /// an endpoint on a path that only differs in parameter names is matched first
+    pub fn lint(&self) -> Vec<Lint> {
+        find_lints(&self.handlers())
+    }
+
+    /// The paths and methods of all endpoints in the route tree
+    fn handlers(&self) -> Vec<(&Path, Option<Method>)> {
+        let local_handlers = self
+            .endpoints
+            .iter()
+            .map(|handler| (&self.path, handler.method));
+
+        let sub_handlers = self.branches.iter().flat_map(RouteSegment::handlers);
 
-        routes
+        local_handlers.chain(sub_handlers).collect()
     }
 
     pub(crate) fn build(self) -> Vec<RouteDescriptor<State>> {
+        self.build_inherited(&Metadata::default())
+    }
+
+    fn build_inherited(self, metadata: &Metadata) -> Vec<RouteDescriptor<State>> {
         let path = self.path;
         let middleware = self.middleware;
+        let metadata = self.metadata.inherit(metadata);
 
-        let local_endpoints =
-            self.endpoints
-                .into_iter()
-                .map(|(method, endpoint)| RouteDescriptor {
-                    path: path.clone(),
-                    middleware: middleware.clone(),
-                    route: Route::Handler(method, endpoint),
-                });
+        let local_endpoints = self.endpoints.into_iter().map(|handler| RouteDescriptor {
+            path: path.clone(),
+            middleware: middleware.clone(),
+            metadata: metadata.clone(),
+            route: Route::Handler(handler),
+        });
 
-        let sub_endpoints = self.branches.into_iter().flat_map(RouteSegment::build);
+        let sub_endpoints = self
+            .branches
+            .into_iter()
+            .flat_map(|branch| branch.build_inherited(&metadata))
+            .collect::<Vec<_>>();
 
         local_endpoints.chain(sub_endpoints).collect()
     }
+
+    /// Add a branch with the given path and the middleware of this segment
+    fn branch<R: FnOnce(SubRoute<State>) -> SubRoute<State>>(
+        mut self,
+        path: Path,
+        routes: R,
+    ) -> SubRoute<State> {
+        self.branches.push(routes(Ok(RouteSegment {
+            path,
+            middleware: self.middleware.clone(),
+            name: None,
+            typed: false,
+            namespace: None,
+            metadata: Metadata::default(),
+            branches: Vec::new(),
+            endpoints: Vec::new(),
+        }))?);
+        Ok(self)
+    }
 }
 
 impl<State: Clone + Send + Sync + 'static> RouteBuilder<State> for Result<RouteSegment<State>> {
     fn at<R: FnOnce(Self) -> Self>(self, path: &str, routes: R) -> Self {
-        let mut segment = self?;
+        let segment = self?;
+        let path = segment.path.clone().append(path)?;
 
-        segment.branches.push(routes(Ok(RouteSegment {
-            path: segment.path.clone().append(path),
-            middleware: segment.middleware.clone(),
-            name: None,
-            branches: Vec::new(),
-            endpoints: HashMap::new(),
-        }))?);
-        Ok(segment)
+        segment.branch(path, routes)
+    }
+
+    fn at_matching<R: FnOnce(Self) -> Self>(
+        self,
+        path: &str,
+        constraint: Constraint,
+        routes: R,
+    ) -> Self {
+        let segment = self?;
+        let path = segment.path.clone().append(path)?.constrain(constraint)?;
+
+        segment.branch(path, routes)
     }
 
     fn with<M: Middleware<State>, R: FnOnce(Self) -> Self>(self, middleware: M, routes: R) -> Self {
@@ -111,37 +344,204 @@ impl<State: Clone + Send + Sync + 'static> RouteBuilder<State> for Result<RouteS
             path: segment.path.clone(),
             middleware: ware,
             name: None,
+            typed: false,
+            namespace: None,
+            metadata: Metadata::default(),
             branches: Vec::new(),
-            endpoints: HashMap::new(),
+            endpoints: Vec::new(),
         }))?);
         Ok(segment)
     }
 
+    #[track_caller]
     fn method(self, method: Method, endpoint: impl Endpoint<State>) -> Self {
+        let location = Location::caller();
         let mut segment = self?;
 
-        segment
-            .endpoints
-            .insert(Some(method), BoxedEndpoint::new(endpoint));
+        segment.endpoints.push(Handler {
+            method: Some(method),
+            name: None,
+            endpoint: HandlerEndpoint::Endpoint(BoxedEndpoint::new(endpoint)),
+            operation: None,
+            location,
+        });
         Ok(segment)
     }
 
+    #[track_caller]
+    fn method_named(self, method: Method, name: &str, endpoint: impl Endpoint<State>) -> Self {
+        let location = Location::caller();
+        let mut segment = self?;
+
+        segment.endpoints.push(Handler {
+            method: Some(method),
+            name: Some(name.to_string()),
+            endpoint: HandlerEndpoint::Endpoint(BoxedEndpoint::new(endpoint)),
+            operation: None,
+            location,
+        });
+        Ok(segment)
+    }
+
+    #[track_caller]
     fn all(self, endpoint: impl Endpoint<State>) -> Self {
+        let location = Location::caller();
+        let mut segment = self?;
+
+        segment.endpoints.push(Handler {
+            method: None,
+            name: None,
+            endpoint: HandlerEndpoint::Endpoint(BoxedEndpoint::new(endpoint)),
+            operation: None,
+            location,
+        });
+        Ok(segment)
+    }
+
+    fn located_at(self, location: &'static Location<'static>) -> Self {
+        let mut segment = self?;
+
+        if let Some(handler) = segment.endpoints.last_mut() {
+            handler.location = location;
+        }
+        Ok(segment)
+    }
+
+    fn meta<T: Any + Send + Sync>(self, value: T) -> Self {
         let mut segment = self?;
 
-        segment.endpoints.insert(None, BoxedEndpoint::new(endpoint));
+        segment.metadata.insert(value);
         Ok(segment)
     }
 
     fn name(self, name: &str) -> Self {
         let mut segment = self?;
 
-        if let Some(name) = segment.name {
-            panic!("route already has name: {}", name);
+        if let Some(existing) = segment.name {
+            return Err(RouteError::AlreadyNamed {
+                name: existing,
+                rejected: name.to_string(),
+            }
+            .into());
         }
         segment.name = Some(name.to_string());
         Ok(segment)
     }
+
+    fn typed_name<R: TypedRoute>(self) -> Self {
+        let mut segment = self.name(R::NAME)?;
+
+        let mut params = segment.path.params();
+        let mut expected = R::PARAMS.to_vec();
+        params.sort_unstable();
+        expected.sort_unstable();
+
+        if params != expected {
+            return Err(RouteError::TypedRouteParams {
+                name: R::NAME.to_string(),
+                path: segment.path.to_string(),
+                params: R::PARAMS.iter().map(ToString::to_string).collect(),
+            }
+            .into());
+        }
+
+        segment.typed = true;
+        Ok(segment)
+    }
+
+    fn namespace(self, namespace: &str) -> Self {
+        let mut segment = self?;
+
+        if let Some(existing) = segment.namespace {
+            return Err(RouteError::AlreadyNamespaced {
+                namespace: existing,
+                rejected: namespace.to_string(),
+            }
+            .into());
+        }
+        segment.namespace = Some(namespace.to_string());
+        Ok(segment)
+    }
+}
+
+impl<State: Clone + Send + Sync + 'static> RouteDocsExt<State> for Result<RouteSegment<State>> {
+    #[track_caller]
+    fn serve_route_table(self) -> Self {
+        let location = Location::caller();
+        let mut segment = self?;
+
+        segment.endpoints.push(Handler {
+            method: Some(Method::Get),
+            name: None,
+            endpoint: HandlerEndpoint::RouteTable,
+            operation: None,
+            location,
+        });
+        Ok(segment)
+    }
+
+    #[track_caller]
+    fn serve_openapi(self, info: Info) -> Self {
+        let location = Location::caller();
+        let mut segment = self?;
+
+        segment.endpoints.push(Handler {
+            method: Some(Method::Get),
+            name: None,
+            endpoint: HandlerEndpoint::OpenApi(info),
+            operation: Some(Operation::new().exclude()),
+            location,
+        });
+        Ok(segment)
+    }
+
+    fn document(self, operation: Operation) -> Self {
+        let mut segment = self?;
+
+        match segment.endpoints.last_mut() {
+            Some(handler) => handler.operation = Some(operation),
+            None => {
+                return Err(RouteError::NothingToDocument {
+                    path: segment.path.to_string(),
+                }
+                .into())
+            }
+        }
+        Ok(segment)
+    }
+}
+
+/// Prefix a name with a namespace separated by a dot
+fn qualify(namespace: Option<&str>, name: &str) -> String {
+    match namespace {
+        Some(namespace) => format!("{}.{}", namespace, name),
+        None => name.to_string(),
+    }
+}
+
+/// Describes an endpoint in a route tree
+#[derive(Debug, Clone)]
+pub struct RouteInfo {
+    /// The full path of the endpoint
+    pub path: Path,
+
+    /// The method the endpoint is registered for, `None` for catch-all endpoints
+    pub method: Option<Method>,
+
+    /// The name of the endpoint or of the path it is registered on, including namespaces
+    pub name: Option<String>,
+
+    /// The type names of the middleware that runs for the endpoint in the order it runs
+    pub middleware: Vec<String>,
+
+    /// The metadata of the route, including metadata inherited from parent segments
+    pub metadata: Metadata,
+
+    /// Documentation for the endpoint added with `document`
+    pub operation: Option<Operation>,
+
+    /// Where in the source the endpoint was added to the route tree
+    pub location: &'static Location<'static>,
 }
 
 /// Partial routing results for passing around in routing closures
@@ -152,12 +552,33 @@ pub type SubRoute<T> = Result<RouteSegment<T>>;
 pub(crate) struct RouteDescriptor<State> {
     pub(crate) path: Path,
     pub(crate) middleware: Vec<ArcMiddleware<State>>,
+    pub(crate) metadata: Metadata,
     pub(crate) route: Route<State>,
 }
 
 /// Descibes a leaf in the route tree, either a name or a handler
 #[derive(Debug)]
 pub(crate) enum Route<State> {
-    Name(String),
-    Handler(Option<Method>, BoxedEndpoint<State>),
+    Name(String, Option<Method>),
+    Handler(Handler<State>),
+}
+
+/// An endpoint in the route tree, the method it handles, its name and where in the source it was
+/// added
+#[derive(Debug)]
+pub(crate) struct Handler<State> {
+    pub(crate) method: Option<Method>,
+    pub(crate) name: Option<String>,
+    pub(crate) endpoint: HandlerEndpoint<State>,
+    pub(crate) operation: Option<Operation>,
+    pub(crate) location: &'static Location<'static>,
+}
+
+/// The endpoint of a handler, endpoints that serve information about the route tree itself are
+/// constructed when the route tree is registered
+#[derive(Debug)]
+pub(crate) enum HandlerEndpoint<State> {
+    Endpoint(BoxedEndpoint<State>),
+    RouteTable,
+    OpenApi(Info),
 }
@@ -0,0 +1,43 @@
+//! Fixtures and helpers shared by the tests of the modules
+
+use tide::http::{self, Method, Url};
+use tide::{Request, StatusCode};
+
+/// An endpoint that responds with `first`
+pub(crate) async fn endpoint(_: Request<()>) -> tide::Result {
+    Ok("first".into())
+}
+
+/// An endpoint that responds with `second`
+pub(crate) async fn other_endpoint(_: Request<()>) -> tide::Result {
+    Ok("second".into())
+}
+
+/// A request without a body for a path on `http://example.com`
+pub(crate) fn request(method: Method, path: &str) -> http::Request {
+    let url = Url::parse("http://example.com")
+        .unwrap()
+        .join(path)
+        .unwrap();
+    http::Request::new(method, url)
+}
+
+/// The response of the server to a request without a body
+pub(crate) async fn respond(
+    server: &tide::Server<()>,
+    method: Method,
+    path: &str,
+) -> http::Response {
+    server.respond(request(method, path)).await.unwrap()
+}
+
+/// The status and body of the response of the server to a request without a body
+pub(crate) async fn call(
+    server: &tide::Server<()>,
+    method: Method,
+    path: &str,
+) -> (StatusCode, String) {
+    let mut response = respond(server, method, path).await;
+
+    (response.status(), response.body_string().await.unwrap())
+}
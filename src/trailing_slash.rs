@@ -0,0 +1,279 @@
+//! Policies for requests that only differ from a route in their trailing slash. Tide treats
+//! `/articles` and `/articles/` as different routes, the path as written in the route tree is the
+//! canonical form and the policy decides what happens to the other form. A policy can be set for
+//! the whole tree using `RegisterOptions::trailing_slash` and replaced for a subtree with
+//! `RouteBuilderExt::trailing_slash`;
+//! ```rust
+//! # use tide::{Request, Result};
+//! # async fn endpoint(_: Request<()>) -> Result {
+//! #     todo!()
+//! # }
+//! use tide_fluent_routes::prelude::*;
+//!
+//! let mut server = tide::Server::new();
+//!
+//! server.register_with(
+//!     root()
+//!         .at("articles", |route| route.get(endpoint))
+//!         .at("legacy/", |route| route
+//!             .trailing_slash(TrailingSlash::Both)
+//!             .get(endpoint)
+//!         ),
+//!     RegisterOptions::new().trailing_slash(TrailingSlash::PermanentRedirect),
+//! ).expect("Error setting up routes");
+//! ```
+//! The reverse router always resolves the canonical form. Redirects keep OPTIONS endpoints for
+//! both forms, browsers don't follow redirects for CORS preflight requests.
+
+use crate::constraint::ConstrainedRoute;
+use crate::path::Path;
+use crate::util::{BoxedEndpoint, SharedEndpoint};
+use tide::http::headers::LOCATION;
+use tide::http::Method;
+use tide::{utils::async_trait, Endpoint, Request, Response, StatusCode};
+
+/// What to do with requests that only differ from a route in their trailing slash
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TrailingSlash {
+    /// Only the path as written matches
+    #[default]
+    Strict,
+
+    /// Both forms are registered with the same endpoints and middleware
+    Both,
+
+    /// The other form is redirected to the canonical form with a 301 Moved Permanently
+    Redirect,
+
+    /// The other form is redirected to the canonical form with a 308 Permanent Redirect, clients
+    /// keep the method and body of the request
+    PermanentRedirect,
+}
+
+/// Add routes for the other form of every path with a policy that is not strict, paths that are
+/// declared in both forms are left alone. The first policy for a path is used. OPTIONS endpoints
+/// are shared instead of redirected.
+pub(crate) fn alternatives<State: Clone + Send + Sync + 'static>(
+    mut routes: Vec<ConstrainedRoute<State>>,
+    policies: Vec<(Path, TrailingSlash)>,
+) -> Vec<ConstrainedRoute<State>> {
+    let mut done: Vec<Path> = Vec::new();
+
+    for (path, policy) in policies {
+        if done.contains(&path) {
+            continue;
+        }
+        done.push(path.clone());
+
+        let other = match path.toggle_trailing_slash() {
+            Some(other) => other,
+            None => continue,
+        };
+        if routes.iter().any(|route| route.path == other) {
+            continue;
+        }
+
+        let status = match policy {
+            TrailingSlash::Strict => continue,
+            TrailingSlash::Both => {
+                routes = share_routes(routes, &path, &other, |_| true);
+                continue;
+            }
+            TrailingSlash::Redirect => StatusCode::MovedPermanently,
+            TrailingSlash::PermanentRedirect => StatusCode::PermanentRedirect,
+        };
+
+        routes = share_routes(routes, &path, &other, |method| {
+            method == Some(Method::Options)
+        });
+        routes.push(ConstrainedRoute {
+            path: other,
+            method: None,
+            middleware: Vec::new(),
+            endpoint: BoxedEndpoint::new(RedirectEndpoint(status)),
+            fallback: false,
+        });
+    }
+
+    routes
+}
+
+/// Register the endpoints of a path for the other path as well, only for the selected methods
+fn share_routes<State: Clone + Send + Sync + 'static>(
+    routes: Vec<ConstrainedRoute<State>>,
+    path: &Path,
+    other: &Path,
+    select: impl Fn(Option<Method>) -> bool,
+) -> Vec<ConstrainedRoute<State>> {
+    let mut shared = Vec::new();
+
+    let mut routes: Vec<ConstrainedRoute<State>> = routes
+        .into_iter()
+        .map(|route| {
+            if route.path != *path || !select(route.method) {
+                return route;
+            }
+
+            let endpoint = SharedEndpoint::new(route.endpoint);
+            shared.push(ConstrainedRoute {
+                path: other.clone(),
+                method: route.method,
+                middleware: route.middleware.clone(),
+                endpoint: BoxedEndpoint::new(endpoint.clone()),
+                fallback: route.fallback,
+            });
+            ConstrainedRoute {
+                endpoint: BoxedEndpoint::new(endpoint),
+                ..route
+            }
+        })
+        .collect();
+
+    routes.extend(shared);
+    routes
+}
+
+/// Redirects a request to the same url with the trailing slash added or removed. The location is
+/// relative to the request, so it stays correct when the routes are nested in another server that
+/// strips its prefix from the url.
+#[derive(Debug)]
+struct RedirectEndpoint(StatusCode);
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for RedirectEndpoint {
+    async fn call(&self, request: Request<State>) -> tide::Result {
+        let url = request.url();
+
+        let mut location = match url.path().strip_suffix('/') {
+            Some(path) => format!("../{}", last_segment(path)),
+            None => format!("./{}/", last_segment(url.path())),
+        };
+        if let Some(query) = url.query() {
+            location.push('?');
+            location.push_str(query);
+        }
+
+        let mut response = Response::new(self.0);
+        response.insert_header(LOCATION, location);
+        Ok(response)
+    }
+}
+
+/// The last segment of a path
+fn last_segment(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::params;
+    use crate::prelude::*;
+    use crate::test_util::{endpoint, respond};
+    use tide::http::Url;
+
+    async fn link_to_article(request: Request<()>) -> tide::Result {
+        Ok(request
+            .url_for("article", params! {"id" => request.param("id")?})?
+            .into())
+    }
+
+    #[async_std::test]
+    async fn should_only_match_path_as_written_by_default() {
+        let mut server = tide::Server::new();
+        server
+            .register(root().at("articles", |r| r.get(endpoint)))
+            .unwrap();
+
+        assert_eq!(
+            respond(&server, Method::Get, "/articles").await.status(),
+            StatusCode::Ok
+        );
+        assert_eq!(
+            respond(&server, Method::Get, "/articles/").await.status(),
+            StatusCode::NotFound
+        );
+    }
+
+    #[async_std::test]
+    async fn should_register_both_forms() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles/:id", |r| r.name("article").get(link_to_article)),
+                RegisterOptions::new()
+                    .with_reverse_router()
+                    .trailing_slash(TrailingSlash::Both),
+            )
+            .unwrap();
+
+        let mut response = respond(&server, Method::Get, "/articles/12/").await;
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert_eq!(response.body_string().await.unwrap(), "/articles/12");
+    }
+
+    #[async_std::test]
+    async fn should_redirect_to_canonical_form() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root()
+                    .at("articles", |r| r.get(endpoint))
+                    .at("legacy/", |r| {
+                        r.trailing_slash(TrailingSlash::Redirect).get(endpoint)
+                    }),
+                RegisterOptions::new().trailing_slash(TrailingSlash::PermanentRedirect),
+            )
+            .unwrap();
+
+        let response = respond(&server, Method::Get, "/articles/?page=2").await;
+        assert_eq!(response.status(), StatusCode::PermanentRedirect);
+        assert_eq!(response.header(LOCATION).unwrap(), "../articles?page=2");
+
+        let response = respond(&server, Method::Get, "/legacy").await;
+        assert_eq!(response.status(), StatusCode::MovedPermanently);
+        assert_eq!(response.header(LOCATION).unwrap(), "./legacy/");
+
+        assert_eq!(
+            respond(&server, Method::Get, "/legacy/").await.status(),
+            StatusCode::Ok
+        );
+    }
+
+    #[async_std::test]
+    async fn should_redirect_relative_to_nested_server() {
+        let mut blog = tide::Server::new();
+        blog.register_with(
+            root().at("articles", |r| r.get(endpoint)),
+            RegisterOptions::new().trailing_slash(TrailingSlash::Redirect),
+        )
+        .unwrap();
+        let mut server = tide::Server::new();
+        server.at("/blog").nest(blog);
+
+        let response = respond(&server, Method::Get, "/blog/articles/").await;
+        let location = Url::parse("http://example.com/blog/articles/")
+            .unwrap()
+            .join(response.header(LOCATION).unwrap().as_str())
+            .unwrap();
+        assert_eq!(location.path(), "/blog/articles");
+    }
+
+    #[async_std::test]
+    async fn should_keep_paths_declared_in_both_forms() {
+        let mut server = tide::Server::new();
+        server
+            .register_with(
+                root().at("articles", |r| {
+                    r.get(|_| async { Ok("without") })
+                        .at("/", |r| r.get(|_| async { Ok("with") }))
+                }),
+                RegisterOptions::new().trailing_slash(TrailingSlash::Redirect),
+            )
+            .unwrap();
+
+        let mut response = respond(&server, Method::Get, "/articles/").await;
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert_eq!(response.body_string().await.unwrap(), "with");
+    }
+}
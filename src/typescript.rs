@@ -0,0 +1,354 @@
+//! Generates a TypeScript module with a function for every named route. Frontends can use these
+//! functions to build urls instead of duplicating the paths of the backend;
+//! ```rust
+//! # use tide::{Request, Result};
+//! # async fn endpoint(_: Request<()>) -> Result {
+//! #     todo!()
+//! # }
+//! use tide_fluent_routes::prelude::*;
+//! use tide_fluent_routes::typescript::typescript_module;
+//!
+//! let routes = root::<()>()
+//!     .at("articles/:id", |route| route.name("article").get(endpoint))
+//!     .expect("Error setting up routes");
+//!
+//! let module = typescript_module(&routes.reverse_router().expect("Invalid route names"))
+//!     .expect("Route names that become the same function name");
+//! assert!(module.contains("export function article(params: { id: __Param }): string"));
+//! ```
+
+use crate::error::RouteError;
+use crate::path::{Path, Segment};
+use crate::reverse_router::ReverseRouter;
+use crate::Result;
+
+/// Words that can not be used as function names in a module, function names that are reserved
+/// get an underscore appended
+const RESERVED: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// The helpers of the module start with two underscores, function names for routes never do
+const HEADER: &str = "// Generated by tide-fluent-routes, do not edit
+
+type __Param = string | number;
+
+function __encode(value: __Param): string {
+  return encodeURIComponent(String(value));
+}
+
+function __encodeWildcard(value: __Param): string {
+  return String(value).split(\"/\").map(encodeURIComponent).join(\"/\");
+}
+";
+
+/// Generate a TypeScript module with a function for every named route in the reverse router. The
+/// functions take the parameters of the route and return the url, they are named after the route
+/// converted to camel case so `articles.show` becomes `articlesShow`. Fails when more than one
+/// route name becomes the same function name.
+pub fn typescript_module(router: &ReverseRouter) -> Result<String> {
+    let routes: Vec<(&str, &Path, String)> = router
+        .paths()
+        .into_iter()
+        .map(|(name, path)| (name, path, function_name(name)))
+        .collect();
+
+    for (index, (name, _, function)) in routes.iter().enumerate() {
+        let others: Vec<String> = routes[index + 1..]
+            .iter()
+            .filter(|(_, _, other)| other == function)
+            .map(|(other, _, _)| other.to_string())
+            .collect();
+
+        if !others.is_empty() {
+            let mut names = vec![name.to_string()];
+            names.extend(others);
+            return Err(RouteError::DuplicateFunctionNames {
+                function: function.clone(),
+                names,
+            }
+            .into());
+        }
+    }
+
+    let mut module = HEADER.to_string();
+
+    for (name, path, function) in routes {
+        let params = path.params();
+
+        let path_comment = comment(&path.to_string());
+        match router.method(name) {
+            Some(method) => module.push_str(&format!("\n/** {} {} */\n", method, path_comment)),
+            None => module.push_str(&format!("\n/** {} */\n", path_comment)),
+        }
+        if params.is_empty() {
+            module.push_str(&format!("export function {}(): string {{\n", function));
+        } else {
+            let params: Vec<String> = params
+                .iter()
+                .map(|param| format!("{}: __Param", property(param)))
+                .collect();
+            module.push_str(&format!(
+                "export function {}(params: {{ {} }}): string {{\n",
+                function,
+                params.join("; ")
+            ));
+        }
+        module.push_str(&format!("  return `{}`;\n}}\n", template(path)));
+    }
+
+    Ok(module)
+}
+
+/// Convert a route name to a camel case function name that is not a reserved word
+fn function_name(name: &str) -> String {
+    let mut function = String::new();
+
+    for (index, word) in name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .enumerate()
+    {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            if index == 0 {
+                function.push(first.to_ascii_lowercase());
+            } else {
+                function.push(first.to_ascii_uppercase());
+            }
+            function.extend(chars);
+        }
+    }
+
+    if function.chars().next().map_or(true, |c| c.is_ascii_digit()) {
+        function.insert(0, '_');
+    }
+    if RESERVED.contains(&function.as_str()) {
+        function.push('_');
+    }
+    function
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// A parameter as a property name in an object type
+fn property(param: &str) -> String {
+    if is_identifier(param) {
+        param.to_string()
+    } else {
+        format!("{:?}", param)
+    }
+}
+
+/// A parameter as an expression reading it from the params object
+fn access(param: &str) -> String {
+    if is_identifier(param) {
+        format!("params.{}", param)
+    } else {
+        format!("params[{:?}]", param)
+    }
+}
+
+/// Escape text for a doc comment so it can't end the comment
+fn comment(text: &str) -> String {
+    text.replace("*/", "*\\/")
+}
+
+/// Convert a path to the contents of a template literal
+fn template(path: &Path) -> String {
+    let segments: Vec<String> = path
+        .iter()
+        .map(|segment| match segment {
+            Segment::Param { name, .. } => format!("${{__encode({})}}", access(name)),
+            Segment::Wildcard(name) => format!("${{__encodeWildcard({})}}", access(name)),
+            Segment::Static(segment) => segment
+                .replace('\\', "\\\\")
+                .replace('`', "\\`")
+                .replace('$', "\\$"),
+        })
+        .collect();
+
+    path.join(&segments)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tide::http::Method;
+
+    #[test]
+    fn should_generate_module() {
+        let mut router = ReverseRouter::new();
+        router.insert("root", "/").unwrap();
+        router.insert("articles.show", "/articles/:id").unwrap();
+        router.insert("files", "/files/*path").unwrap();
+        router
+            .insert_method("articles.create", Method::Post, "/articles")
+            .unwrap();
+
+        let expected = HEADER.to_string()
+            + "
+/** POST /articles */
+export function articlesCreate(): string {
+  return `/articles`;
+}
+
+/** /articles/:id */
+export function articlesShow(params: { id: __Param }): string {
+  return `/articles/${__encode(params.id)}`;
+}
+
+/** /files/*path */
+export function files(params: { path: __Param }): string {
+  return `/files/${__encodeWildcard(params.path)}`;
+}
+
+/** / */
+export function root(): string {
+  return `/`;
+}
+";
+
+        assert_eq!(typescript_module(&router).unwrap(), expected);
+    }
+
+    #[test]
+    fn should_convert_route_names_to_function_names() {
+        assert_eq!(function_name("article"), "article");
+        assert_eq!(
+            function_name("api.v1.Article-comments"),
+            "apiV1ArticleComments"
+        );
+        assert_eq!(function_name("404"), "_404");
+    }
+
+    #[test]
+    fn should_escape_reserved_words() {
+        assert_eq!(function_name("delete"), "delete_");
+        assert_eq!(function_name("new"), "new_");
+        assert_eq!(function_name("Default"), "default_");
+        assert_eq!(function_name("newest"), "newest");
+    }
+
+    #[test]
+    fn should_fail_on_route_names_with_same_function_name() {
+        let mut router = ReverseRouter::new();
+        router.insert("articles.show", "/articles/:id").unwrap();
+        router.insert("articles_show", "/posts/:id").unwrap();
+
+        let error = typescript_module(&router).unwrap_err();
+        assert_eq!(
+            error.downcast::<RouteError>().unwrap(),
+            RouteError::DuplicateFunctionNames {
+                function: "articlesShow".to_string(),
+                names: vec!["articles.show".to_string(), "articles_show".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn should_quote_params_that_are_not_identifiers() {
+        assert_eq!(
+            template(&Path::parse("/users/:user-id").unwrap()),
+            "/users/${__encode(params[\"user-id\"])}"
+        );
+        assert_eq!(property("user-id"), "\"user-id\"");
+    }
+
+    #[test]
+    fn should_name_unnamed_wildcard() {
+        assert_eq!(
+            template(&Path::parse("/files/*").unwrap()),
+            "/files/${__encodeWildcard(params.path)}"
+        );
+    }
+
+    #[test]
+    fn should_keep_helpers_apart_from_route_functions() {
+        let mut router = ReverseRouter::new();
+        router.insert("encode", "/encode").unwrap();
+        router.insert("encode.wildcard", "/encode/*path").unwrap();
+        router.insert("param", "/param/:id").unwrap();
+
+        let module = typescript_module(&router).unwrap();
+        assert!(module.contains("export function encode(): string"));
+        assert!(
+            module.contains("export function encodeWildcard(params: { path: __Param }): string")
+        );
+        assert!(module.contains("export function param(params: { id: __Param }): string"));
+        assert_eq!(module.matches("function encode(").count(), 1);
+        assert_eq!(module.matches("function encodeWildcard(").count(), 1);
+    }
+
+    #[test]
+    fn should_escape_end_of_comment_in_paths() {
+        let mut router = ReverseRouter::new();
+        router.insert("star", "/a*/b").unwrap();
+
+        let module = typescript_module(&router).unwrap();
+        assert!(
+            module.contains("/** /a*\\/b */\nexport function star(): string {\n  return `/a*/b`;")
+        );
+    }
+
+    #[test]
+    fn should_escape_static_segments() {
+        assert_eq!(
+            template(&Path::parse("/price/$5/`x`").unwrap()),
+            "/price/\\$5/\\`x\\`"
+        );
+    }
+}
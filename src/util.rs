@@ -28,6 +28,29 @@ impl<State: Clone + Send + Sync + 'static> Endpoint<State> for BoxedEndpoint<Sta
     }
 }
 
+/// A BoxedEndpoint that can be registered more than once
+pub(crate) struct SharedEndpoint<State>(Arc<BoxedEndpoint<State>>);
+
+impl<State: Clone + Send + Sync + 'static> SharedEndpoint<State> {
+    /// Share a BoxedEndpoint
+    pub(crate) fn new(endpoint: BoxedEndpoint<State>) -> Self {
+        Self(Arc::new(endpoint))
+    }
+}
+
+impl<State> Clone for SharedEndpoint<State> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for SharedEndpoint<State> {
+    async fn call(&self, req: tide::Request<State>) -> tide::Result {
+        self.0.call(req).await
+    }
+}
+
 /// Implement some useful stuff around Arc<dyn Middleware>
 #[derive(Clone)]
 pub struct ArcMiddleware<State>(Arc<dyn Middleware<State>>);
@@ -39,6 +62,13 @@ impl<State: Clone + Send + Sync + 'static> ArcMiddleware<State> {
     }
 }
 
+impl<State> ArcMiddleware<State> {
+    /// Check if both are the same middleware, not just middleware of the same type
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 impl<State> Debug for ArcMiddleware<State> {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
         formatter.debug_struct("ArcMiddleware").finish()
@@ -54,4 +84,8 @@ impl<State: Clone + Send + Sync + 'static> Middleware<State> for ArcMiddleware<S
     ) -> tide::Result {
         self.0.handle(request, next).await
     }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
 }